@@ -0,0 +1,60 @@
+use criterion::{Criterion, criterion_group, criterion_main};
+use dicom_volume::{Interpolation, Orientation, Processor, Volume};
+use ndarray::{Array3, Axis};
+use std::hint::black_box;
+
+const PROCESSORS: [(&str, Processor); 2] = [("cpu", Processor::CPU), ("simd", Processor::SIMD)];
+
+/// 512×512×1000 volume with anisotropic spacing
+fn test_volume() -> Volume {
+    let data = Array3::from_shape_fn((1000, 512, 512), |(z, y, x)| {
+        ((z * 7 + y * 3 + x) % 4096) as f32 * 16.0
+    });
+    Volume::new(data, (0.7, 0.7, 1.0))
+}
+
+fn bench_interpolation(c: &mut Criterion) {
+    let volume = test_volume();
+
+    let mut group = c.benchmark_group("coronal_bilinear_image");
+    group.sample_size(10);
+    for (name, processor) in PROCESSORS {
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                volume.get_image_from_axis(
+                    black_box(256),
+                    Orientation::Coronal,
+                    Interpolation::Bilinear(processor.clone()),
+                )
+            })
+        });
+    }
+    group.finish();
+
+    let mut group = c.benchmark_group("coronal_trilinear_at_position");
+    group.sample_size(10);
+    for (name, processor) in PROCESSORS {
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                volume.get_slice_at_position(
+                    black_box(255.5),
+                    Orientation::Coronal,
+                    Interpolation::Trilinear(processor.clone()),
+                )
+            })
+        });
+    }
+    group.finish();
+
+    let slice = volume.data.index_axis(Axis(0), 500);
+    let mut group = c.benchmark_group("axial_to_u8");
+    for (name, processor) in PROCESSORS {
+        group.bench_function(name, |b| {
+            b.iter(|| processor.backend().to_u8(black_box(&slice)))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_interpolation);
+criterion_main!(benches);
@@ -0,0 +1,751 @@
+//! Compute backends used by [`Volume`] operations.
+//!
+//! A backend is selected through the [`Processor`] of an [`Interpolation`].
+//! Third parties can implement [`ComputeBackend`] and either pass it as
+//! [`Processor::Custom`] or make it available by name through
+//! [`register_backend`].
+//!
+//! [`Processor`]: crate::enums::Processor
+//! [`Processor::Custom`]: crate::enums::Processor::Custom
+
+use crate::enums::Interpolation;
+use crate::enums::ProjectionMode;
+use crate::geometry::Point3;
+use crate::interpolator::Interpolator;
+use crate::interpolator::within_bounds;
+use crate::volume::Volume;
+
+use ndarray::Array;
+use ndarray::Array2;
+use ndarray::Array3;
+use ndarray::ArrayView1;
+use ndarray::ArrayView2;
+use ndarray::ArrayView3;
+use ndarray::ArrayViewMut1;
+use ndarray::Axis;
+use ndarray::Dimension;
+use ndarray::Zip;
+use rayon::iter::IntoParallelIterator;
+use rayon::iter::ParallelIterator;
+use std::sync::Arc;
+use std::sync::RwLock;
+
+static REGISTRY: RwLock<Vec<Arc<dyn ComputeBackend>>> = RwLock::new(Vec::new());
+
+/// Make a backend available through [`Processor::named`]. A backend with the
+/// same name replaces the previously registered one.
+///
+/// [`Processor::named`]: crate::enums::Processor::named
+pub fn register_backend(backend: Arc<dyn ComputeBackend>) {
+    let mut registry = REGISTRY.write().unwrap_or_else(|e| e.into_inner());
+    registry.retain(|registered| registered.name() != backend.name());
+    registry.push(backend);
+}
+
+pub(crate) fn find_backend(name: &str) -> Option<Arc<dyn ComputeBackend>> {
+    let registry = REGISTRY.read().unwrap_or_else(|e| e.into_inner());
+    registry
+        .iter()
+        .find(|backend| backend.name() == name)
+        .cloned()
+}
+
+/// A regular grid of sample positions on a plane through the volume
+///
+/// All positions are continuous voxel coordinates (x = column, y = row,
+/// z = slice). The sample at (row, col) lies at
+/// `origin + row_step * row + col_step * col`.
+#[derive(Clone, Copy, Debug)]
+pub struct PlaneGrid {
+    pub origin: Point3,
+    pub row_step: Point3,
+    pub col_step: Point3,
+    /// (height, width) of the resulting plane
+    pub dim: (usize, usize),
+}
+
+impl PlaneGrid {
+    #[inline]
+    pub fn position(&self, row: usize, col: usize) -> Point3 {
+        self.origin + self.row_step * row as f32 + self.col_step * col as f32
+    }
+
+    /// Get the volume axis (0 = depth, 1 = height, 2 = width) the plane is
+    /// normal to, `None` for oblique planes
+    pub(crate) fn normal_axis(&self) -> Option<usize> {
+        let components = |point: Point3| [point.z, point.y, point.x];
+        let (row, col) = (components(self.row_step), components(self.col_step));
+        (0..3).find(|&axis| row[axis] == 0.0 && col[axis] == 0.0)
+    }
+}
+
+/// Get the grid and interpolation to sample a plane with
+///
+/// `Bilinear` interpolates within the plane: an axis-aligned plane is moved
+/// onto the nearest voxel plane along its normal and sampled trilinearly,
+/// which is exact bilinear interpolation within that voxel plane. Oblique
+/// planes are sampled trilinearly. Other interpolations are kept.
+pub(crate) fn plane_sampling(
+    grid: &PlaneGrid,
+    interpolation: &Interpolation,
+) -> (PlaneGrid, Interpolation) {
+    let Interpolation::Bilinear(processor) = interpolation else {
+        return (*grid, interpolation.clone());
+    };
+    let mut grid = *grid;
+    match grid.normal_axis() {
+        Some(0) => grid.origin.z = grid.origin.z.round(),
+        Some(1) => grid.origin.y = grid.origin.y.round(),
+        Some(_) => grid.origin.x = grid.origin.x.round(),
+        None => {}
+    }
+    (grid, Interpolation::Trilinear(processor.clone()))
+}
+
+/// Operations a compute backend has to provide
+///
+/// Samples outside of the volume are 0. For `BSpline` the volumes to sample
+/// hold the B-spline coefficients from [`Volume::bspline_coefficients`],
+/// while slices are prefiltered by the backend. The default implementations
+/// delegate to [`CpuBackend`], so custom backends only need to override the
+/// operations they accelerate.
+///
+/// [`Volume::bspline_coefficients`]: crate::volume::Volume::bspline_coefficients
+pub trait ComputeBackend: Send + Sync {
+    /// Name used to look up the backend through [`Processor::named`]
+    ///
+    /// [`Processor::named`]: crate::enums::Processor::named
+    fn name(&self) -> &str;
+
+    /// Resample a 2D plane to (height, width), mapping the corner samples
+    /// onto each other
+    fn resample_slice(
+        &self,
+        slice: &ArrayView2<'_, f32>,
+        dim: (usize, usize),
+        interpolation: &Interpolation,
+    ) -> Array2<f32> {
+        CpuBackend.resample_slice(slice, dim, interpolation)
+    }
+
+    /// Sample the volume on a regular plane grid
+    fn resample_plane(
+        &self,
+        volume: &ArrayView3<'_, f32>,
+        grid: &PlaneGrid,
+        interpolation: &Interpolation,
+    ) -> Array2<f32> {
+        CpuBackend.resample_plane(volume, grid, interpolation)
+    }
+
+    /// Sample the volume at arbitrary positions in voxel coordinates
+    fn sample_points(
+        &self,
+        volume: &ArrayView3<'_, f32>,
+        points: &ArrayView2<'_, Point3>,
+        interpolation: &Interpolation,
+    ) -> Array2<f32> {
+        CpuBackend.sample_points(volume, points, interpolation)
+    }
+
+    /// Resample the volume to (depth, height, width), where output voxel
+    /// (k, i, j) samples the input at `(j * step.x, i * step.y, k * step.z)`
+    fn resample_volume(
+        &self,
+        volume: &ArrayView3<'_, f32>,
+        dim: (usize, usize, usize),
+        step: Point3,
+        interpolation: &Interpolation,
+    ) -> Array3<f32> {
+        CpuBackend.resample_volume(volume, dim, step, interpolation)
+    }
+
+    /// Project the volume along an axis (0 = depth, 1 = height, 2 = width)
+    fn project(
+        &self,
+        volume: &ArrayView3<'_, f32>,
+        axis: usize,
+        mode: ProjectionMode,
+    ) -> Array2<f32> {
+        CpuBackend.project(volume, axis, mode)
+    }
+
+    /// Convolve the volume with a centred 1D kernel of odd length along an
+    /// axis, repeating the border samples
+    fn convolve(&self, volume: &ArrayView3<'_, f32>, axis: usize, kernel: &[f32]) -> Array3<f32> {
+        CpuBackend.convolve(volume, axis, kernel)
+    }
+
+    /// Convert 16-bit range intensities to 8-bit pixels in row-major order
+    fn to_u8(&self, values: &ArrayView2<'_, f32>) -> Vec<u8> {
+        CpuBackend.to_u8(values)
+    }
+}
+
+/// Multi-threaded CPU backend using rayon
+pub struct CpuBackend;
+
+/// Single-threaded reference backend
+///
+/// Evaluates every sample in plain loops, independently of the other
+/// backends, so they can be tested against it.
+pub struct ScalarBackend;
+
+/// Fill a new array by evaluating `f` at every index in parallel
+fn fill<D, F>(shape: D, f: F) -> Array<f32, D>
+where
+    D: Dimension + Copy,
+    D::Pattern: Send,
+    F: Fn(D::Pattern) -> f32 + Sync + Send,
+{
+    let mut result = Array::<f32, D>::zeros(shape);
+    Zip::indexed(&mut result).par_for_each(|index, value| *value = f(index));
+    result
+}
+
+fn resample_slice(
+    slice: &ArrayView2<'_, f32>,
+    dim: (usize, usize),
+    interpolation: &Interpolation,
+) -> Array2<f32> {
+    let source = Interpolator::prepare_slice(slice.view(), interpolation);
+    let source = source.view();
+    let (height, width) = source.dim();
+    if height == 0 || width == 0 {
+        return Array2::zeros(dim);
+    }
+    let max_y = (height - 1) as f32;
+    let max_x = (width - 1) as f32;
+    let scale_y = max_y / (dim.0.max(2) - 1) as f32;
+    let scale_x = max_x / (dim.1.max(2) - 1) as f32;
+
+    fill(ndarray::Ix2(dim.0, dim.1), |(row, col)| {
+        let y = (row as f32 * scale_y).min(max_y);
+        let x = (col as f32 * scale_x).min(max_x);
+        Interpolator::sample_slice(&source, y, x, interpolation)
+    })
+}
+
+#[inline]
+fn sample(volume: &ArrayView3<'_, f32>, position: Point3, interpolation: &Interpolation) -> f32 {
+    Interpolator::sample_volume(volume, position.z, position.y, position.x, interpolation)
+        .unwrap_or(0.0)
+}
+
+fn resample_plane(
+    volume: &ArrayView3<'_, f32>,
+    grid: &PlaneGrid,
+    interpolation: &Interpolation,
+) -> Array2<f32> {
+    let (grid, interpolation) = plane_sampling(grid, interpolation);
+    fill(ndarray::Ix2(grid.dim.0, grid.dim.1), |(row, col)| {
+        sample(volume, grid.position(row, col), &interpolation)
+    })
+}
+
+fn sample_points(
+    volume: &ArrayView3<'_, f32>,
+    points: &ArrayView2<'_, Point3>,
+    interpolation: &Interpolation,
+) -> Array2<f32> {
+    let (height, width) = points.dim();
+    fill(ndarray::Ix2(height, width), |index| {
+        sample(volume, points[index], interpolation)
+    })
+}
+
+fn resample_volume(
+    volume: &ArrayView3<'_, f32>,
+    dim: (usize, usize, usize),
+    step: Point3,
+    interpolation: &Interpolation,
+) -> Array3<f32> {
+    fill(ndarray::Ix3(dim.0, dim.1, dim.2), |(k, i, j)| {
+        let position = Point3::new(j as f32, i as f32, k as f32).scale((step.x, step.y, step.z));
+        sample(volume, position, interpolation)
+    })
+}
+
+fn project(volume: &ArrayView3<'_, f32>, axis: usize, mode: ProjectionMode) -> Array2<f32> {
+    Zip::from(volume.lanes(Axis(axis))).par_map_collect(|lane| mode.reduce(lane))
+}
+
+fn convolve_line(input: ArrayView1<'_, f32>, kernel: &[f32], mut output: ArrayViewMut1<'_, f32>) {
+    let len = input.len() as isize;
+    let radius = (kernel.len() / 2) as isize;
+    for (i, value) in output.iter_mut().enumerate() {
+        *value = kernel
+            .iter()
+            .enumerate()
+            .map(|(k, weight)| {
+                let index = (i as isize + k as isize - radius).clamp(0, len - 1);
+                input[index as usize] * weight
+            })
+            .sum();
+    }
+}
+
+fn convolve(volume: &ArrayView3<'_, f32>, axis: usize, kernel: &[f32]) -> Array3<f32> {
+    let mut result = Array3::<f32>::zeros(volume.raw_dim());
+    if volume.is_empty() {
+        return result;
+    }
+    Zip::from(result.lanes_mut(Axis(axis)))
+        .and(volume.lanes(Axis(axis)))
+        .par_for_each(|output, input| convolve_line(input, kernel, output));
+    result
+}
+
+impl ComputeBackend for CpuBackend {
+    fn name(&self) -> &str {
+        "cpu"
+    }
+
+    fn resample_slice(
+        &self,
+        slice: &ArrayView2<'_, f32>,
+        dim: (usize, usize),
+        interpolation: &Interpolation,
+    ) -> Array2<f32> {
+        resample_slice(slice, dim, interpolation)
+    }
+
+    fn resample_plane(
+        &self,
+        volume: &ArrayView3<'_, f32>,
+        grid: &PlaneGrid,
+        interpolation: &Interpolation,
+    ) -> Array2<f32> {
+        resample_plane(volume, grid, interpolation)
+    }
+
+    fn sample_points(
+        &self,
+        volume: &ArrayView3<'_, f32>,
+        points: &ArrayView2<'_, Point3>,
+        interpolation: &Interpolation,
+    ) -> Array2<f32> {
+        sample_points(volume, points, interpolation)
+    }
+
+    fn resample_volume(
+        &self,
+        volume: &ArrayView3<'_, f32>,
+        dim: (usize, usize, usize),
+        step: Point3,
+        interpolation: &Interpolation,
+    ) -> Array3<f32> {
+        resample_volume(volume, dim, step, interpolation)
+    }
+
+    fn project(
+        &self,
+        volume: &ArrayView3<'_, f32>,
+        axis: usize,
+        mode: ProjectionMode,
+    ) -> Array2<f32> {
+        project(volume, axis, mode)
+    }
+
+    fn convolve(&self, volume: &ArrayView3<'_, f32>, axis: usize, kernel: &[f32]) -> Array3<f32> {
+        convolve(volume, axis, kernel)
+    }
+
+    fn to_u8(&self, values: &ArrayView2<'_, f32>) -> Vec<u8> {
+        (0..values.nrows())
+            .into_par_iter()
+            .flat_map_iter(|row| {
+                values
+                    .row(row)
+                    .iter()
+                    .map(|&v| Volume::normalize_to_u8(v))
+                    .collect::<Vec<u8>>()
+            })
+            .collect()
+    }
+}
+
+/// Sample bilinearly within the voxel plane normal to `axis` nearest to
+/// `position`, 0 outside of the volume
+fn sample_within_plane(volume: &ArrayView3<'_, f32>, axis: usize, position: Point3) -> f32 {
+    let coordinates = [position.z, position.y, position.x];
+    let index = coordinates[axis].round();
+    if volume.is_empty() || index < 0.0 || index > (volume.len_of(Axis(axis)) - 1) as f32 {
+        return 0.0;
+    }
+    let plane = volume.index_axis(Axis(axis), index as usize);
+    let (height, width) = plane.dim();
+    let (rows, cols) = match axis {
+        0 => (coordinates[1], coordinates[2]),
+        1 => (coordinates[0], coordinates[2]),
+        _ => (coordinates[0], coordinates[1]),
+    };
+    if !(within_bounds(rows, height) && within_bounds(cols, width)) {
+        return 0.0;
+    }
+    let y = rows.clamp(0.0, (height - 1) as f32);
+    let x = cols.clamp(0.0, (width - 1) as f32);
+    Interpolator::bilinear_interpolate(&plane, y, x)
+}
+
+impl ComputeBackend for ScalarBackend {
+    fn name(&self) -> &str {
+        "scalar"
+    }
+
+    fn resample_slice(
+        &self,
+        slice: &ArrayView2<'_, f32>,
+        dim: (usize, usize),
+        interpolation: &Interpolation,
+    ) -> Array2<f32> {
+        let mut result = Array2::zeros(dim);
+        let source = Interpolator::prepare_slice(slice.view(), interpolation);
+        let (height, width) = source.dim();
+        if height == 0 || width == 0 {
+            return result;
+        }
+
+        let max_y = (height - 1) as f32;
+        let max_x = (width - 1) as f32;
+        for row in 0..dim.0 {
+            for col in 0..dim.1 {
+                // Map the corner samples onto each other
+                let y = row as f32 * (max_y / (dim.0.max(2) - 1) as f32);
+                let x = col as f32 * (max_x / (dim.1.max(2) - 1) as f32);
+                result[[row, col]] = Interpolator::sample_slice(
+                    &source.view(),
+                    y.min(max_y),
+                    x.min(max_x),
+                    interpolation,
+                );
+            }
+        }
+        result
+    }
+
+    fn resample_plane(
+        &self,
+        volume: &ArrayView3<'_, f32>,
+        grid: &PlaneGrid,
+        interpolation: &Interpolation,
+    ) -> Array2<f32> {
+        let mut result = Array2::zeros(grid.dim);
+        for row in 0..grid.dim.0 {
+            for col in 0..grid.dim.1 {
+                let position =
+                    grid.origin + grid.row_step * row as f32 + grid.col_step * col as f32;
+                result[[row, col]] = match (interpolation, grid.normal_axis()) {
+                    (Interpolation::Bilinear(_), Some(axis)) => {
+                        sample_within_plane(volume, axis, position)
+                    }
+                    _ => sample(volume, position, interpolation),
+                };
+            }
+        }
+        result
+    }
+
+    fn sample_points(
+        &self,
+        volume: &ArrayView3<'_, f32>,
+        points: &ArrayView2<'_, Point3>,
+        interpolation: &Interpolation,
+    ) -> Array2<f32> {
+        let mut result = Array2::zeros(points.dim());
+        for ((row, col), point) in points.indexed_iter() {
+            result[[row, col]] = sample(volume, *point, interpolation);
+        }
+        result
+    }
+
+    fn resample_volume(
+        &self,
+        volume: &ArrayView3<'_, f32>,
+        dim: (usize, usize, usize),
+        step: Point3,
+        interpolation: &Interpolation,
+    ) -> Array3<f32> {
+        let mut result = Array3::zeros(dim);
+        for k in 0..dim.0 {
+            for i in 0..dim.1 {
+                for j in 0..dim.2 {
+                    let position =
+                        Point3::new(j as f32 * step.x, i as f32 * step.y, k as f32 * step.z);
+                    result[[k, i, j]] = sample(volume, position, interpolation);
+                }
+            }
+        }
+        result
+    }
+
+    fn project(
+        &self,
+        volume: &ArrayView3<'_, f32>,
+        axis: usize,
+        mode: ProjectionMode,
+    ) -> Array2<f32> {
+        let (depth, height, width) = volume.dim();
+        let (len, dim) = match axis {
+            0 => (depth, (height, width)),
+            1 => (height, (depth, width)),
+            _ => (width, (depth, height)),
+        };
+
+        let mut result = Array2::zeros(dim);
+        for row in 0..dim.0 {
+            for col in 0..dim.1 {
+                let mut value = match mode {
+                    ProjectionMode::Maximum => f32::NEG_INFINITY,
+                    ProjectionMode::Minimum => f32::INFINITY,
+                    ProjectionMode::Mean | ProjectionMode::Sum => 0.0,
+                };
+                for i in 0..len {
+                    let sample = match axis {
+                        0 => volume[[i, row, col]],
+                        1 => volume[[row, i, col]],
+                        _ => volume[[row, col, i]],
+                    };
+                    value = match mode {
+                        ProjectionMode::Maximum => value.max(sample),
+                        ProjectionMode::Minimum => value.min(sample),
+                        ProjectionMode::Mean | ProjectionMode::Sum => value + sample,
+                    };
+                }
+                if matches!(mode, ProjectionMode::Mean) {
+                    value /= len as f32;
+                }
+                result[[row, col]] = value;
+            }
+        }
+        result
+    }
+
+    fn convolve(&self, volume: &ArrayView3<'_, f32>, axis: usize, kernel: &[f32]) -> Array3<f32> {
+        let mut result = Array3::zeros(volume.raw_dim());
+        let len = volume.len_of(Axis(axis)) as isize;
+        let radius = (kernel.len() / 2) as isize;
+        for ((z, y, x), value) in result.indexed_iter_mut() {
+            let mut index = [z, y, x];
+            let center = index[axis] as isize;
+            let mut sum = 0.0;
+            for (k, weight) in kernel.iter().enumerate() {
+                // Repeat the border samples
+                index[axis] = (center + k as isize - radius).clamp(0, len - 1) as usize;
+                sum += volume[index] * weight;
+            }
+            *value = sum;
+        }
+        result
+    }
+
+    fn to_u8(&self, values: &ArrayView2<'_, f32>) -> Vec<u8> {
+        let mut pixels = Vec::with_capacity(values.len());
+        for row in values.rows() {
+            for &value in row {
+                pixels.push(Volume::normalize_to_u8(value));
+            }
+        }
+        pixels
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::enums::Processor;
+
+    fn test_data() -> Array3<f32> {
+        Array3::from_shape_fn((6, 7, 8), |(z, y, x)| {
+            ((z * 131 + y * 71 + x * 37) % 97) as f32 * 600.0
+        })
+    }
+
+    fn interpolations() -> Vec<Interpolation> {
+        vec![
+            Interpolation::None,
+            Interpolation::NearestNeighbor(Processor::CPU),
+            Interpolation::Bilinear(Processor::CPU),
+            Interpolation::Trilinear(Processor::CPU),
+            Interpolation::Cubic(Processor::CPU),
+            Interpolation::BSpline(Processor::CPU),
+            Interpolation::Lanczos(Processor::CPU),
+        ]
+    }
+
+    #[test]
+    fn test_backends_resample_identically() {
+        let data = test_data();
+        let view = data.view();
+        let grid = PlaneGrid {
+            origin: Point3::new(0.5, 1.0, 2.3),
+            row_step: Point3::new(0.0, 0.4, 0.1),
+            col_step: Point3::new(0.6, 0.0, 0.0),
+            dim: (14, 12),
+        };
+        let points = Array2::from_shape_fn((3, 5), |(i, j)| {
+            Point3::new(j as f32 * 1.7, i as f32 * 2.9, 0.3 + j as f32)
+        });
+
+        for interpolation in interpolations() {
+            assert_eq!(
+                CpuBackend.resample_slice(&view.index_axis(Axis(1), 3), (11, 17), &interpolation),
+                ScalarBackend.resample_slice(
+                    &view.index_axis(Axis(1), 3),
+                    (11, 17),
+                    &interpolation
+                )
+            );
+            assert_eq!(
+                CpuBackend.resample_plane(&view, &grid, &interpolation),
+                ScalarBackend.resample_plane(&view, &grid, &interpolation)
+            );
+            assert_eq!(
+                CpuBackend.sample_points(&view, &points.view(), &interpolation),
+                ScalarBackend.sample_points(&view, &points.view(), &interpolation)
+            );
+            assert_eq!(
+                CpuBackend.resample_volume(
+                    &view,
+                    (9, 5, 8),
+                    Point3::new(1.0, 1.5, 0.6),
+                    &interpolation
+                ),
+                ScalarBackend.resample_volume(
+                    &view,
+                    (9, 5, 8),
+                    Point3::new(1.0, 1.5, 0.6),
+                    &interpolation
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn test_backends_sample_linear_ramp_exactly() {
+        let data = Array3::from_shape_fn((6, 7, 8), |(z, y, x)| (3 * z + 2 * y + x) as f32);
+        let view = data.view();
+        let ramp = |p: Point3| 3.0 * p.z + 2.0 * p.y + p.x;
+        let oblique = PlaneGrid {
+            origin: Point3::new(0.5, 1.0, 2.3),
+            row_step: Point3::new(0.0, 0.35, 0.1),
+            col_step: Point3::new(0.55, 0.0, 0.0),
+            dim: (14, 12),
+        };
+        // Coronal plane between two rows
+        let coronal = PlaneGrid {
+            origin: Point3::new(0.0, 2.4, 0.0),
+            row_step: Point3::new(0.0, 0.0, 0.5),
+            col_step: Point3::new(0.5, 0.0, 0.0),
+            dim: (11, 15),
+        };
+        let trilinear = Interpolation::Trilinear(Processor::CPU);
+        let bilinear = Interpolation::Bilinear(Processor::CPU);
+        let backends: [&dyn ComputeBackend; 2] = [&CpuBackend, &ScalarBackend];
+
+        for backend in backends {
+            let plane = backend.resample_plane(&view, &oblique, &trilinear);
+            let snapped = backend.resample_plane(&view, &coronal, &bilinear);
+            let step = Point3::new(0.7, 1.5, 0.6);
+            let volume = backend.resample_volume(&view, (9, 5, 8), step, &trilinear);
+
+            for ((row, col), &value) in plane.indexed_iter() {
+                assert!((value - ramp(oblique.position(row, col))).abs() < 1e-4);
+            }
+            for ((row, col), &value) in snapped.indexed_iter() {
+                let mut position = coronal.position(row, col);
+                position.y = 2.0;
+                assert!((value - ramp(position)).abs() < 1e-4);
+            }
+            for ((k, i, j), &value) in volume.indexed_iter() {
+                let position = Point3::new(j as f32, i as f32, k as f32).scale((0.7, 1.5, 0.6));
+                assert!((value - ramp(position)).abs() < 1e-4);
+            }
+        }
+    }
+
+    #[test]
+    fn test_backends_project_and_convert_identically() {
+        let data = test_data();
+        let view = data.view();
+
+        for mode in [
+            ProjectionMode::Maximum,
+            ProjectionMode::Minimum,
+            ProjectionMode::Mean,
+            ProjectionMode::Sum,
+        ] {
+            for axis in 0..3 {
+                assert_eq!(
+                    CpuBackend.project(&view, axis, mode),
+                    ScalarBackend.project(&view, axis, mode)
+                );
+            }
+        }
+
+        let sagittal = view.index_axis(Axis(2), 4);
+        assert_eq!(CpuBackend.to_u8(&sagittal), ScalarBackend.to_u8(&sagittal));
+    }
+
+    #[test]
+    fn test_backends_convolve_identically() {
+        let data = test_data();
+        let view = data.view();
+        let kernel = [0.25, 0.5, 0.25];
+
+        for axis in 0..3 {
+            assert_eq!(
+                CpuBackend.convolve(&view, axis, &kernel),
+                ScalarBackend.convolve(&view, axis, &kernel)
+            );
+        }
+    }
+
+    #[test]
+    fn test_convolve_repeats_border() {
+        let data = Array3::from_shape_vec((1, 1, 3), vec![1.0, 2.0, 6.0]).unwrap();
+
+        let result = ScalarBackend.convolve(&data.view(), 2, &[0.5, 0.0, 0.5]);
+
+        assert_eq!(result.into_raw_vec_and_offset().0, vec![1.5, 3.5, 4.0]);
+    }
+
+    struct DoublingBackend;
+
+    impl ComputeBackend for DoublingBackend {
+        fn name(&self) -> &str {
+            "doubling"
+        }
+
+        fn project(
+            &self,
+            volume: &ArrayView3<'_, f32>,
+            axis: usize,
+            mode: ProjectionMode,
+        ) -> Array2<f32> {
+            CpuBackend.project(volume, axis, mode) * 2.0
+        }
+    }
+
+    #[test]
+    fn test_register_custom_backend() {
+        register_backend(Arc::new(DoublingBackend));
+        let data = test_data();
+
+        let processor = Processor::named("doubling").unwrap();
+        let result = processor
+            .backend()
+            .project(&data.view(), 0, ProjectionMode::Maximum);
+
+        assert_eq!(
+            result,
+            CpuBackend.project(&data.view(), 0, ProjectionMode::Maximum) * 2.0
+        );
+        // Operations that aren't overridden fall back to the CPU backend
+        assert_eq!(
+            processor.backend().to_u8(&data.index_axis(Axis(0), 1)),
+            CpuBackend.to_u8(&data.index_axis(Axis(0), 1))
+        );
+        assert!(Processor::named("unknown").is_none());
+    }
+}
@@ -0,0 +1,552 @@
+use crate::enums::Interpolation;
+use crate::enums::Orientation;
+use crate::enums::ProjectionMode;
+use crate::reformat::CurvedPlanarReformat;
+use crate::volume::Volume;
+
+use dicom::core::PrimitiveValue;
+use dicom::object::InMemDicomObject;
+use dicom_dictionary_std::tags;
+use image::ImageBuffer;
+use image::Rgb;
+use image::Rgba;
+use ndarray::ArrayView2;
+use std::sync::LazyLock;
+
+/// Default window, the 16-bit range also used for grey images
+const DEFAULT_WINDOW: (f32, f32) = (0.0, 65535.0);
+
+/// Colour stops of the built-in colour maps as (position, RGB)
+type Stops = &'static [(f32, [u8; 3])];
+
+const GRAYSCALE: Stops = &[(0.0, [0, 0, 0]), (1.0, [255, 255, 255])];
+
+/// Black, red, yellow, white like the DICOM Hot Iron palette
+const HOT_IRON: Stops = &[
+    (0.0, [0, 0, 0]),
+    (0.5, [255, 0, 0]),
+    (0.75, [255, 128, 0]),
+    (1.0, [255, 255, 255]),
+];
+
+/// Black, blue, green, orange, white like the DICOM PET palette
+const PET: Stops = &[
+    (0.0, [0, 0, 0]),
+    (0.25, [0, 128, 255]),
+    (0.5, [128, 255, 128]),
+    (0.75, [255, 128, 0]),
+    (1.0, [255, 255, 255]),
+];
+
+const RAINBOW: Stops = &[
+    (0.0, [128, 0, 255]),
+    (0.2, [0, 0, 255]),
+    (0.4, [0, 255, 255]),
+    (0.6, [0, 255, 0]),
+    (0.8, [255, 255, 0]),
+    (1.0, [255, 0, 0]),
+];
+
+const JET: Stops = &[
+    (0.0, [0, 0, 128]),
+    (0.125, [0, 0, 255]),
+    (0.375, [0, 255, 255]),
+    (0.625, [255, 255, 0]),
+    (0.875, [255, 0, 0]),
+    (1.0, [128, 0, 0]),
+];
+
+const VIRIDIS: Stops = &[
+    (0.0, [68, 1, 84]),
+    (0.125, [71, 44, 122]),
+    (0.25, [59, 81, 139]),
+    (0.375, [44, 113, 142]),
+    (0.5, [33, 144, 141]),
+    (0.625, [39, 173, 129]),
+    (0.75, [92, 200, 99]),
+    (0.875, [170, 220, 50]),
+    (1.0, [253, 231, 37]),
+];
+
+/// DICOM Palette Color Lookup Table, indexed by the pixel values
+#[derive(Clone, Debug)]
+pub struct PaletteColorLut {
+    /// Pixel value mapped to the first entry
+    pub first_mapped: f32,
+    pub red: Vec<u16>,
+    pub green: Vec<u16>,
+    pub blue: Vec<u16>,
+    /// Number of bits of each entry (8 or 16)
+    pub bits: u16,
+}
+
+impl PaletteColorLut {
+    /// Read the red, green and blue Palette Color Lookup Tables of a DICOM
+    /// object. Returns `None` if any of them is missing or malformed.
+    pub fn from_dicom_object(dicom_object: &InMemDicomObject) -> Option<Self> {
+        let descriptor = dicom_object
+            .element(tags::RED_PALETTE_COLOR_LOOKUP_TABLE_DESCRIPTOR)
+            .ok()?
+            .to_multi_int::<i32>()
+            .ok()?;
+        let [entries, first_mapped, bits] = descriptor[..] else {
+            return None;
+        };
+        // 0 entries stands for 2^16
+        let entries = if entries == 0 {
+            65536
+        } else {
+            entries as usize
+        };
+
+        let data = |tag| -> Option<Vec<u16>> {
+            let values = match dicom_object.element(tag).ok()?.value().primitive()? {
+                PrimitiveValue::U16(values) => values.to_vec(),
+                PrimitiveValue::U8(bytes) => bytes
+                    .chunks_exact(2)
+                    .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+                    .collect(),
+                _ => return None,
+            };
+            (values.len() >= entries).then(|| values[..entries].to_vec())
+        };
+
+        Some(Self {
+            first_mapped: first_mapped as f32,
+            red: data(tags::RED_PALETTE_COLOR_LOOKUP_TABLE_DATA)?,
+            green: data(tags::GREEN_PALETTE_COLOR_LOOKUP_TABLE_DATA)?,
+            blue: data(tags::BLUE_PALETTE_COLOR_LOOKUP_TABLE_DATA)?,
+            bits: bits as u16,
+        })
+    }
+
+    fn len(&self) -> usize {
+        self.red.len().min(self.green.len()).min(self.blue.len())
+    }
+
+    fn entry(&self, index: usize) -> [u8; 3] {
+        let to_u8 = |value: u16| {
+            if self.bits > 8 {
+                (value >> (self.bits.min(16) - 8)) as u8
+            } else if value > 255 {
+                // 8-bit entries stored in the high byte
+                (value >> 8) as u8
+            } else {
+                value as u8
+            }
+        };
+        [
+            to_u8(self.red[index]),
+            to_u8(self.green[index]),
+            to_u8(self.blue[index]),
+        ]
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub enum Colormap {
+    #[default]
+    Grayscale,
+    HotIron,
+    Rainbow,
+    Viridis,
+    Jet,
+    Pet,
+    Palette(PaletteColorLut),
+}
+
+impl Colormap {
+    /// Get the 256 colours of a built-in colour map, sampled once. Palettes
+    /// are indexed directly and have no table.
+    fn table(&self) -> &'static [[u8; 3]] {
+        static TABLES: LazyLock<[Vec<[u8; 3]>; 6]> = LazyLock::new(|| {
+            [GRAYSCALE, HOT_IRON, RAINBOW, VIRIDIS, JET, PET].map(ColorMapping::stops_to_table)
+        });
+        match self {
+            Colormap::Grayscale => &TABLES[0],
+            Colormap::HotIron => &TABLES[1],
+            Colormap::Rainbow => &TABLES[2],
+            Colormap::Viridis => &TABLES[3],
+            Colormap::Jet => &TABLES[4],
+            Colormap::Pet => &TABLES[5],
+            Colormap::Palette(_) => &[],
+        }
+    }
+}
+
+/// Mapping of intensities to colours
+///
+/// Intensities between `window.0` and `window.1` are spread over the colour
+/// map. Without a window, the 16-bit range is used like for grey images,
+/// and palettes are indexed by the intensities directly.
+#[derive(Clone, Debug)]
+pub struct ColorMapping {
+    pub colormap: Colormap,
+    /// (lower, upper) intensity of the window
+    pub window: Option<(f32, f32)>,
+    /// Reverse the colour map
+    pub inverted: bool,
+    /// Alpha of RGBA output in 0..=1
+    pub opacity: f32,
+}
+
+impl Default for ColorMapping {
+    fn default() -> Self {
+        Self::new(Colormap::default())
+    }
+}
+
+impl ColorMapping {
+    pub fn new(colormap: Colormap) -> Self {
+        Self {
+            colormap,
+            window: None,
+            inverted: false,
+            opacity: 1.0,
+        }
+    }
+
+    pub fn with_window(mut self, lower: f32, upper: f32) -> Self {
+        self.window = Some((lower, upper));
+        self
+    }
+
+    pub fn with_inverted(mut self, inverted: bool) -> Self {
+        self.inverted = inverted;
+        self
+    }
+
+    pub fn with_opacity(mut self, opacity: f32) -> Self {
+        self.opacity = opacity;
+        self
+    }
+
+    /// Position of an intensity within the window in 0..=1
+    fn normalize(value: f32, (lower, upper): (f32, f32)) -> f32 {
+        if upper > lower {
+            ((value - lower) / (upper - lower)).clamp(0.0, 1.0)
+        } else {
+            (value >= upper) as u8 as f32
+        }
+    }
+
+    /// Sample the colour stops into a table of 256 colours
+    fn stops_to_table(stops: Stops) -> Vec<[u8; 3]> {
+        (0..256)
+            .map(|i| {
+                let t = i as f32 / 255.0;
+                let index = stops.partition_point(|(position, _)| *position < t);
+                if index == 0 {
+                    return stops[0].1;
+                }
+                let (p0, c0) = stops[index - 1];
+                let (p1, c1) = stops[index.min(stops.len() - 1)];
+                let f = if p1 > p0 { (t - p0) / (p1 - p0) } else { 0.0 };
+                std::array::from_fn(|c| {
+                    (c0[c] as f32 + (c1[c] as f32 - c0[c] as f32) * f).round() as u8
+                })
+            })
+            .collect()
+    }
+
+    /// Get a function mapping intensities to colours
+    pub(crate) fn mapper(&self) -> impl Fn(f32) -> [u8; 3] + '_ {
+        let table = self.colormap.table();
+        let (len, first_mapped) = match &self.colormap {
+            Colormap::Palette(palette) => (palette.len(), Some(palette.first_mapped)),
+            _ => (table.len(), None),
+        };
+
+        move |value: f32| {
+            let Some(last) = len.checked_sub(1) else {
+                return [0; 3];
+            };
+            let index = match (self.window, first_mapped) {
+                (None, Some(first_mapped)) => (value - first_mapped).round().max(0.0) as usize,
+                (window, _) => {
+                    let t = Self::normalize(value, window.unwrap_or(DEFAULT_WINDOW));
+                    // Truncate like the grey conversion
+                    (t * last as f32) as usize
+                }
+            };
+            let index = index.min(last);
+            let index = if self.inverted { last - index } else { index };
+            match &self.colormap {
+                Colormap::Palette(palette) => palette.entry(index),
+                _ => table[index],
+            }
+        }
+    }
+
+    /// Get the colour of an intensity
+    pub fn color(&self, value: f32) -> [u8; 3] {
+        self.mapper()(value)
+    }
+
+    /// Convert a plane of intensities to an RGB image
+    pub fn to_rgb_image(
+        &self,
+        values: &ArrayView2<'_, f32>,
+    ) -> Option<ImageBuffer<Rgb<u8>, Vec<u8>>> {
+        let (height, width) = values.dim();
+        let mapper = self.mapper();
+        let pixels = values.iter().flat_map(|&value| mapper(value)).collect();
+        ImageBuffer::from_raw(width as u32, height as u32, pixels)
+    }
+
+    /// Convert a plane of intensities to an RGBA image with the opacity of
+    /// the mapping as alpha
+    pub fn to_rgba_image(
+        &self,
+        values: &ArrayView2<'_, f32>,
+    ) -> Option<ImageBuffer<Rgba<u8>, Vec<u8>>> {
+        let (height, width) = values.dim();
+        let alpha = (self.opacity * 255.0).round().clamp(0.0, 255.0) as u8;
+        let mapper = self.mapper();
+        let pixels = values
+            .iter()
+            .flat_map(|&value| {
+                let [r, g, b] = mapper(value);
+                [r, g, b, alpha]
+            })
+            .collect();
+        ImageBuffer::from_raw(width as u32, height as u32, pixels)
+    }
+}
+
+impl Volume {
+    /// Get a slice as pseudo-colour image
+    pub fn get_color_image_from_axis(
+        &self,
+        index: usize,
+        orientation: Orientation,
+        interpolation: Interpolation,
+        mapping: &ColorMapping,
+    ) -> Option<ImageBuffer<Rgb<u8>, Vec<u8>>> {
+        let slice = self.get_slice_from_axis(index, &orientation)?;
+        let plane = self.stretch_plane(slice, orientation, &interpolation);
+        mapping.to_rgb_image(&plane.view())
+    }
+
+    /// Get a slice as pseudo-colour image with the opacity of the mapping as alpha
+    pub fn get_rgba_image_from_axis(
+        &self,
+        index: usize,
+        orientation: Orientation,
+        interpolation: Interpolation,
+        mapping: &ColorMapping,
+    ) -> Option<ImageBuffer<Rgba<u8>, Vec<u8>>> {
+        let slice = self.get_slice_from_axis(index, &orientation)?;
+        let plane = self.stretch_plane(slice, orientation, &interpolation);
+        mapping.to_rgba_image(&plane.view())
+    }
+
+    /// Get a thick-slab projection of `thickness` mm centred on `index` as
+    /// pseudo-colour image
+    pub fn get_color_projection_image_from_axis(
+        &self,
+        index: usize,
+        thickness: f32,
+        orientation: Orientation,
+        mode: ProjectionMode,
+        interpolation: Interpolation,
+        mapping: &ColorMapping,
+    ) -> Option<ImageBuffer<Rgb<u8>, Vec<u8>>> {
+        let projection = self.get_projection_from_axis(
+            index,
+            thickness,
+            orientation,
+            mode,
+            interpolation.processor(),
+        )?;
+        let plane = self.stretch_plane(projection.view(), orientation, &interpolation);
+        mapping.to_rgb_image(&plane.view())
+    }
+
+    /// Get a projection through the full volume as pseudo-colour image
+    pub fn get_color_full_projection_image(
+        &self,
+        orientation: Orientation,
+        mode: ProjectionMode,
+        interpolation: Interpolation,
+        mapping: &ColorMapping,
+    ) -> Option<ImageBuffer<Rgb<u8>, Vec<u8>>> {
+        let projection = self.get_full_projection(orientation, mode, interpolation.processor())?;
+        let plane = self.stretch_plane(projection.view(), orientation, &interpolation);
+        mapping.to_rgb_image(&plane.view())
+    }
+
+    /// Get a curved planar reformation as pseudo-colour image
+    pub fn get_color_curved_reformat_image(
+        &self,
+        cpr: &CurvedPlanarReformat,
+        interpolation: Interpolation,
+        mapping: &ColorMapping,
+    ) -> Option<ImageBuffer<Rgb<u8>, Vec<u8>>> {
+        let reformat = self.get_curved_reformat(cpr, interpolation)?;
+        mapping.to_rgb_image(&reformat.view())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::enums::Processor;
+    use crate::geometry::Point3;
+    use crate::reformat::CoordinateSpace;
+    use dicom::core::DataElement;
+    use dicom::core::VR;
+    use ndarray::Array2;
+    use ndarray::Array3;
+
+    #[test]
+    fn test_grayscale_matches_grey_image() {
+        let data = Array3::from_shape_fn((2, 3, 4), |(z, y, x)| {
+            (z * 30000 + y * 5000 + x * 77) as f32
+        });
+        let volume = Volume::new(data, (1.0, 1.0, 1.0));
+
+        let grey = volume
+            .get_image_from_axis(1, Orientation::Axial, Interpolation::None)
+            .unwrap();
+        let color = volume
+            .get_color_image_from_axis(
+                1,
+                Orientation::Axial,
+                Interpolation::None,
+                &ColorMapping::default(),
+            )
+            .unwrap();
+
+        for (grey, color) in grey.pixels().zip(color.pixels()) {
+            assert_eq!(color.0, [grey.0[0]; 3]);
+        }
+    }
+
+    #[test]
+    fn test_colormap_window_and_inversion() {
+        let mapping = ColorMapping::new(Colormap::HotIron).with_window(100.0, 200.0);
+
+        assert_eq!(mapping.color(50.0), [0, 0, 0]);
+        assert_eq!(mapping.color(300.0), [255, 255, 255]);
+        assert_eq!(mapping.color(150.0)[1], 0);
+        assert_eq!(
+            mapping.clone().with_inverted(true).color(50.0),
+            [255, 255, 255]
+        );
+        assert_eq!(ColorMapping::new(Colormap::Viridis).color(0.0), [68, 1, 84]);
+    }
+
+    #[test]
+    fn test_rgba_image_uses_opacity() {
+        let values = Array2::from_elem((2, 3), 65535.0);
+        let mapping = ColorMapping::new(Colormap::Jet).with_opacity(0.5);
+
+        let image = mapping.to_rgba_image(&values.view()).unwrap();
+
+        assert_eq!(image.dimensions(), (3, 2));
+        assert_eq!(image.get_pixel(2, 1).0, [128, 0, 0, 128]);
+    }
+
+    #[test]
+    fn test_palette_from_dicom_object() {
+        let mut dicom_object = InMemDicomObject::new_empty();
+        for (descriptor, data, values) in [
+            (
+                tags::RED_PALETTE_COLOR_LOOKUP_TABLE_DESCRIPTOR,
+                tags::RED_PALETTE_COLOR_LOOKUP_TABLE_DATA,
+                [0u16, 0xFFFF, 0x8000],
+            ),
+            (
+                tags::GREEN_PALETTE_COLOR_LOOKUP_TABLE_DESCRIPTOR,
+                tags::GREEN_PALETTE_COLOR_LOOKUP_TABLE_DATA,
+                [0, 0, 0x4000],
+            ),
+            (
+                tags::BLUE_PALETTE_COLOR_LOOKUP_TABLE_DESCRIPTOR,
+                tags::BLUE_PALETTE_COLOR_LOOKUP_TABLE_DATA,
+                [0xFFFF, 0, 0],
+            ),
+        ] {
+            dicom_object.put(DataElement::new(
+                descriptor,
+                VR::US,
+                PrimitiveValue::from([3u16, 10, 16]),
+            ));
+            dicom_object.put(DataElement::new(data, VR::OW, PrimitiveValue::from(values)));
+        }
+
+        let palette = PaletteColorLut::from_dicom_object(&dicom_object).unwrap();
+        let mapping = ColorMapping::new(Colormap::Palette(palette));
+
+        assert_eq!(mapping.color(5.0), [0, 0, 255]);
+        assert_eq!(mapping.color(11.0), [255, 0, 0]);
+        assert_eq!(mapping.color(12.0), [128, 64, 0]);
+        assert_eq!(mapping.color(100.0), [128, 64, 0]);
+        assert_eq!(mapping.with_inverted(true).color(10.0), [128, 64, 0]);
+    }
+
+    fn slab_volume() -> Volume {
+        // Intensity ramps over the 16-bit range along the depth
+        let data = Array3::from_shape_fn((5, 3, 4), |(z, _, x)| (z * 16000 + x) as f32);
+        Volume::new(data, (1.0, 1.0, 2.0))
+    }
+
+    #[test]
+    fn test_color_projection_images() {
+        let volume = slab_volume();
+        let mapping = ColorMapping::new(Colormap::HotIron);
+
+        let slab = volume
+            .get_color_projection_image_from_axis(
+                2,
+                2.0,
+                Orientation::Axial,
+                ProjectionMode::Maximum,
+                Interpolation::None,
+                &mapping,
+            )
+            .unwrap();
+        let full = volume
+            .get_color_full_projection_image(
+                Orientation::Coronal,
+                ProjectionMode::Maximum,
+                Interpolation::Bilinear(Processor::CPU),
+                &mapping,
+            )
+            .unwrap();
+
+        assert_eq!(slab.dimensions(), (4, 3));
+        assert_eq!(slab.get_pixel(0, 0).0, mapping.color(32000.0));
+        // Depth is stretched from 5 slices at 2 mm to 10 rows
+        assert_eq!(full.dimensions(), (4, 10));
+        assert_eq!(full.get_pixel(3, 9).0, mapping.color(64003.0));
+    }
+
+    #[test]
+    fn test_color_curved_reformat_image() {
+        let volume = slab_volume();
+        let mapping = ColorMapping::new(Colormap::Jet).with_window(0.0, 64000.0);
+        let cpr = CurvedPlanarReformat::new(
+            vec![Point3::new(1.0, 1.0, 0.0), Point3::new(1.0, 1.0, 4.0)],
+            CoordinateSpace::Voxel,
+        )
+        .with_width(2.0);
+
+        let image = volume
+            .get_color_curved_reformat_image(&cpr, Interpolation::None, &mapping)
+            .unwrap();
+        let reformat = volume
+            .get_curved_reformat(&cpr, Interpolation::None)
+            .unwrap();
+
+        // 8 mm along the centreline, 2 mm across
+        assert_eq!(image.dimensions(), (9, 3));
+        for ((row, col), &value) in reformat.indexed_iter() {
+            assert_eq!(
+                image.get_pixel(col as u32, row as u32).0,
+                mapping.color(value)
+            );
+        }
+    }
+}
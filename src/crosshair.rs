@@ -0,0 +1,256 @@
+use crate::backend::PlaneGrid;
+use crate::enums::Interpolation;
+use crate::enums::Orientation;
+use crate::geometry::Point3;
+use crate::volume::Volume;
+
+use image::ImageBuffer;
+use image::Rgb;
+
+const ORIENTATIONS: [Orientation; 3] = [
+    Orientation::Axial,
+    Orientation::Coronal,
+    Orientation::Sagittal,
+];
+
+/// Current slice index of each view of a linked MPR layout
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SliceIndices {
+    pub axial: usize,
+    pub coronal: usize,
+    pub sagittal: usize,
+}
+
+impl SliceIndices {
+    pub fn new(axial: usize, coronal: usize, sagittal: usize) -> Self {
+        Self {
+            axial,
+            coronal,
+            sagittal,
+        }
+    }
+
+    /// Get the index of the slice shown in the view of the given orientation
+    pub fn index(&self, orientation: Orientation) -> usize {
+        match orientation {
+            Orientation::Axial => self.axial,
+            Orientation::Coronal => self.coronal,
+            Orientation::Sagittal => self.sagittal,
+        }
+    }
+}
+
+/// Intersection of the slice of another view with an image, in pixel
+/// coordinates (x = column, y = row)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ReferenceLine {
+    /// Orientation of the slice this line represents
+    pub orientation: Orientation,
+    pub start: (f32, f32),
+    pub end: (f32, f32),
+}
+
+impl ReferenceLine {
+    /// Colour conventionally used for the views of each orientation
+    pub fn color(&self) -> [u8; 3] {
+        match self.orientation {
+            Orientation::Axial => [255, 64, 64],
+            Orientation::Coronal => [64, 255, 64],
+            Orientation::Sagittal => [255, 255, 64],
+        }
+    }
+
+    /// Draw the line into an image, rounding to the nearest pixels
+    pub fn draw(&self, image: &mut ImageBuffer<Rgb<u8>, Vec<u8>>) {
+        let (dx, dy) = (self.end.0 - self.start.0, self.end.1 - self.start.1);
+        let steps = dx.abs().max(dy.abs()).ceil().max(1.0) as usize;
+        for step in 0..=steps {
+            let t = step as f32 / steps as f32;
+            let x = (self.start.0 + dx * t).round();
+            let y = (self.start.1 + dy * t).round();
+            if x >= 0.0 && y >= 0.0 && (x as u32) < image.width() && (y as u32) < image.height() {
+                image.put_pixel(x as u32, y as u32, Rgb(self.color()));
+            }
+        }
+    }
+}
+
+/// Get the component of voxel coordinates along a volume axis (depth,
+/// height, width)
+fn axis_component(point: Point3, axis: usize) -> f32 {
+    match axis {
+        0 => point.z,
+        1 => point.y,
+        _ => point.x,
+    }
+}
+
+impl Volume {
+    /// Get the lines where the slices of the other two views intersect the
+    /// slice shown in the view of the given orientation
+    ///
+    /// The lines are in pixel coordinates of [`Volume::get_image_from_axis`]
+    /// with the same interpolation, so they follow the isotropic aspect of
+    /// stretched Coronal and Sagittal images. Returns `None` if an index is
+    /// out of bounds.
+    pub fn get_reference_lines(
+        &self,
+        indices: &SliceIndices,
+        orientation: Orientation,
+        interpolation: &Interpolation,
+    ) -> Option<Vec<ReferenceLine>> {
+        let grid = self.get_plane_grid(
+            indices.index(orientation) as f32,
+            orientation,
+            interpolation,
+        )?;
+
+        ORIENTATIONS
+            .into_iter()
+            .filter(|&other| other != orientation)
+            .map(|other| Self::reference_line(&grid, other, indices.index(other) as f32))
+            .collect()
+    }
+
+    /// Get the line of a slice at an index along the axis of `orientation`
+    /// in the pixel coordinates of a plane grid
+    fn reference_line(
+        grid: &PlaneGrid,
+        orientation: Orientation,
+        index: f32,
+    ) -> Option<ReferenceLine> {
+        let axis = orientation.axis();
+        let (height, width) = grid.dim;
+        let (last_row, last_col) = (height.saturating_sub(1), width.saturating_sub(1));
+        let row_step = axis_component(grid.row_step, axis);
+        let col_step = axis_component(grid.col_step, axis);
+
+        let (start, end, extent) = if row_step != 0.0 {
+            let row = index / row_step;
+            ((0.0, row), (last_col as f32, row), last_row)
+        } else if col_step != 0.0 {
+            let col = index / col_step;
+            ((col, 0.0), (col, last_row as f32), last_col)
+        } else {
+            // A single row or column can only show an index of 0
+            ((0.0, 0.0), (last_col as f32, last_row as f32), 0)
+        };
+        let position = if row_step != 0.0 { start.1 } else { start.0 };
+        if !(0.0..=extent as f32 + 1e-3).contains(&position) {
+            return None;
+        }
+
+        Some(ReferenceLine {
+            orientation,
+            start,
+            end,
+        })
+    }
+
+    /// Get the slice of a view as image with the reference lines of the
+    /// other two views drawn on top, see [`Volume::get_reference_lines`]
+    pub fn get_image_with_reference_lines(
+        &self,
+        indices: &SliceIndices,
+        orientation: Orientation,
+        interpolation: Interpolation,
+    ) -> Option<ImageBuffer<Rgb<u8>, Vec<u8>>> {
+        let lines = self.get_reference_lines(indices, orientation, &interpolation)?;
+        let grey =
+            self.get_image_from_axis(indices.index(orientation), orientation, interpolation)?;
+
+        let (width, height) = grey.dimensions();
+        let pixels = grey
+            .into_raw()
+            .into_iter()
+            .flat_map(|value| [value; 3])
+            .collect();
+        let mut image = ImageBuffer::from_raw(width, height, pixels)?;
+        for line in &lines {
+            line.draw(&mut image);
+        }
+        Some(image)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::enums::Processor;
+    use ndarray::Array3;
+
+    /// 4 slices at 2.5 mm of 5 rows and 6 columns at 1 mm
+    fn test_volume() -> Volume {
+        Volume::new(Array3::zeros((4, 5, 6)), (1.0, 1.0, 2.5))
+    }
+
+    #[test]
+    fn test_axial_lines_in_stretched_coronal_view() {
+        let volume = test_volume();
+        let indices = SliceIndices::new(2, 1, 3);
+        let interpolation = Interpolation::Bilinear(Processor::CPU);
+
+        let lines = volume
+            .get_reference_lines(&indices, Orientation::Coronal, &interpolation)
+            .unwrap();
+        let image = volume
+            .get_image_from_axis(1, Orientation::Coronal, interpolation)
+            .unwrap();
+
+        // Depth is stretched from 4 slices to 10 rows, width stays 6 columns
+        assert_eq!(image.dimensions(), (6, 10));
+        let axial = lines[0];
+        assert_eq!(axial.orientation, Orientation::Axial);
+        assert!((axial.start.1 - 6.0).abs() < 1e-5);
+        assert_eq!((axial.start.0, axial.end.0), (0.0, 5.0));
+        let sagittal = lines[1];
+        assert_eq!(sagittal.orientation, Orientation::Sagittal);
+        assert_eq!((sagittal.start, sagittal.end), ((3.0, 0.0), (3.0, 9.0)));
+    }
+
+    #[test]
+    fn test_axial_view_lines_without_interpolation() {
+        let volume = test_volume();
+
+        let lines = volume
+            .get_reference_lines(
+                &SliceIndices::new(0, 4, 5),
+                Orientation::Axial,
+                &Interpolation::None,
+            )
+            .unwrap();
+
+        assert_eq!((lines[0].start, lines[0].end), ((0.0, 4.0), (5.0, 4.0)));
+        assert_eq!((lines[1].start, lines[1].end), ((5.0, 0.0), (5.0, 4.0)));
+    }
+
+    #[test]
+    fn test_out_of_bounds_index() {
+        let volume = test_volume();
+
+        let lines = volume.get_reference_lines(
+            &SliceIndices::new(4, 0, 0),
+            Orientation::Sagittal,
+            &Interpolation::None,
+        );
+
+        assert!(lines.is_none());
+    }
+
+    #[test]
+    fn test_draws_reference_lines() {
+        let volume = test_volume();
+
+        let image = volume
+            .get_image_with_reference_lines(
+                &SliceIndices::new(1, 2, 3),
+                Orientation::Axial,
+                Interpolation::None,
+            )
+            .unwrap();
+
+        assert_eq!(image.get_pixel(0, 2).0, [64, 255, 64]);
+        assert_eq!(image.get_pixel(3, 0).0, [255, 255, 64]);
+        assert_eq!(image.get_pixel(0, 0).0, [0; 3]);
+    }
+}
@@ -0,0 +1,120 @@
+use crate::morphology::euclidean_distance;
+use crate::volume::Volume;
+
+use ndarray::Zip;
+
+impl Volume {
+    /// Get the Euclidean distance in mm from each voxel to the closest
+    /// non-zero voxel, respecting the voxel spacing
+    ///
+    /// Voxels of the mask are 0. All voxels are infinite for an empty mask.
+    /// A margin of `m` mm around the mask is
+    /// `distance_transform().threshold(0.0, m)`.
+    pub fn distance_transform(&self) -> Volume {
+        let mask = self.data.mapv(|value| value != 0.0);
+        let distance = euclidean_distance(&mask, self.axis_spacing());
+        Volume::new(distance, self.spacing).with_geometry(self.origin, self.direction)
+    }
+
+    /// Get the signed Euclidean distance in mm to the mask boundary
+    ///
+    /// Voxels outside of the mask hold the distance to the closest non-zero
+    /// voxel, voxels inside the negated distance to the closest zero voxel.
+    /// An empty mask is infinite everywhere, a full mask negative infinite.
+    pub fn signed_distance_transform(&self) -> Volume {
+        let mask = self.data.mapv(|value| value != 0.0);
+        let outside = euclidean_distance(&mask, self.axis_spacing());
+        let inside = euclidean_distance(&mask.mapv(|inside| !inside), self.axis_spacing());
+        let distance = Zip::from(&outside)
+            .and(&inside)
+            .par_map_collect(|&outside, &inside| if outside > 0.0 { outside } else { -inside });
+        Volume::new(distance, self.spacing).with_geometry(self.origin, self.direction)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::enums::Morphology;
+    use crate::enums::StructuringElement;
+    use crate::geometry::Point3;
+    use ndarray::Array3;
+    use ndarray::s;
+
+    const SPACING: (f32, f32, f32) = (1.0, 2.0, 3.0);
+
+    /// Mask of two voxels in 1 × 2 × 3 mm voxels
+    fn points() -> Volume {
+        let mut data = Array3::zeros((5, 6, 7));
+        data[[1, 1, 1]] = 1.0;
+        data[[4, 5, 6]] = 1.0;
+        Volume::new(data, SPACING)
+    }
+
+    #[test]
+    fn test_matches_brute_force() {
+        let volume = points();
+        let seeds = [Point3::new(1.0, 1.0, 1.0), Point3::new(6.0, 5.0, 4.0)];
+
+        let distance = volume.distance_transform();
+
+        for ((z, y, x), &value) in distance.data.indexed_iter() {
+            let point = Point3::new(x as f32, y as f32, z as f32);
+            let expected = seeds
+                .iter()
+                .map(|&seed| (point - seed).scale(SPACING).length())
+                .fold(f32::INFINITY, f32::min);
+            assert!((value - expected).abs() < 1e-4, "{value} != {expected}");
+        }
+        assert_eq!(distance.data[[1, 1, 1]], 0.0);
+        assert_eq!(distance.data[[1, 2, 1]], 2.0);
+    }
+
+    #[test]
+    fn test_signed_distance() {
+        let mut volume = Volume::new(Array3::zeros((3, 3, 11)), (1.0, 1.0, 1.0));
+        volume.data_mut().slice_mut(s![.., .., 3..8]).fill(1.0);
+
+        let distance = volume.signed_distance_transform();
+        let row: Vec<f32> = distance.data.slice(s![1, 1, ..]).to_vec();
+
+        assert_eq!(
+            row,
+            vec![3.0, 2.0, 1.0, -1.0, -2.0, -3.0, -2.0, -1.0, 1.0, 2.0, 3.0]
+        );
+    }
+
+    #[test]
+    fn test_margin_matches_dilation() {
+        let mut volume = Volume::new(Array3::zeros((7, 12, 12)), (1.0, 1.0, 2.0));
+        volume.data_mut().slice_mut(s![2..4, 3..6, 4..9]).fill(1.0);
+
+        let margin = volume.distance_transform().threshold(0.0, 2.5);
+        let dilated = volume.morphology(&Morphology::Dilate {
+            radius: 2.5,
+            element: StructuringElement::Sphere,
+        });
+
+        assert_eq!(margin.data, dilated.data);
+    }
+
+    #[test]
+    fn test_empty_and_full_masks() {
+        let empty = Volume::new(Array3::zeros((2, 3, 4)), (1.0, 1.0, 1.0));
+        let full = Volume::new(Array3::ones((2, 3, 4)), (1.0, 1.0, 1.0));
+
+        assert!(
+            empty
+                .distance_transform()
+                .data
+                .iter()
+                .all(|&v| v == f32::INFINITY)
+        );
+        assert!(
+            full.signed_distance_transform()
+                .data
+                .iter()
+                .all(|&v| v == f32::NEG_INFINITY)
+        );
+    }
+}
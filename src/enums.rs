@@ -1,24 +1,104 @@
-#[derive(Clone, Copy)]
+use crate::backend::ComputeBackend;
+use crate::backend::CpuBackend;
+use crate::backend::ScalarBackend;
+use crate::backend::find_backend;
+use crate::simd::SimdBackend;
+
+use std::sync::Arc;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Orientation {
     Axial,
     Coronal,
     Sagittal,
 }
 
-#[derive(Default)]
+impl Orientation {
+    /// Index of the volume axis (depth, height, width) this orientation slices along
+    pub(crate) fn axis(&self) -> usize {
+        match self {
+            Orientation::Axial => 0,
+            Orientation::Coronal => 1,
+            Orientation::Sagittal => 2,
+        }
+    }
+}
+
+#[derive(Clone, Default)]
 pub enum Interpolation {
+    /// Nearest voxel, keeps label values intact
+    NearestNeighbor(Processor),
     Bilinear(Processor),
-    // TODO:
-    // Trilinear(Processor),
-    // Cubic(Processor),
+    Trilinear(Processor),
+    /// Catmull-Rom cubic convolution
+    Cubic(Processor),
+    /// Cubic B-spline, prefiltered to pass through the original samples
+    BSpline(Processor),
+    /// Lanczos windowed sinc with a radius of three samples
+    Lanczos(Processor),
     #[default]
     None,
 }
 
+impl Interpolation {
+    /// Get the processor performing the interpolation, `None` uses the CPU
+    pub fn processor(&self) -> &Processor {
+        static DEFAULT_PROCESSOR: Processor = Processor::CPU;
+        match self {
+            Interpolation::NearestNeighbor(processor)
+            | Interpolation::Bilinear(processor)
+            | Interpolation::Trilinear(processor)
+            | Interpolation::Cubic(processor)
+            | Interpolation::BSpline(processor)
+            | Interpolation::Lanczos(processor) => processor,
+            Interpolation::None => &DEFAULT_PROCESSOR,
+        }
+    }
+}
+
+#[derive(Clone, Default)]
 pub enum Processor {
+    /// Multi-threaded CPU backend using rayon
+    #[default]
     CPU,
-    // TODO:
-    // GPU,
+    /// Multi-threaded CPU backend with AVX2/FMA vectorisation where supported
+    SIMD,
+    /// Single-threaded reference backend
+    Scalar,
+    /// User provided backend
+    Custom(Arc<dyn ComputeBackend>),
+    /// wgpu compute shaders, falls back to the CPU without a usable adapter
+    #[cfg(feature = "gpu")]
+    GPU,
+}
+
+impl Processor {
+    /// Get the backend performing the computations
+    pub fn backend(&self) -> &dyn ComputeBackend {
+        match self {
+            Processor::CPU => &CpuBackend,
+            Processor::SIMD => &SimdBackend,
+            Processor::Scalar => &ScalarBackend,
+            Processor::Custom(backend) => backend.as_ref(),
+            #[cfg(feature = "gpu")]
+            Processor::GPU => match crate::gpu::shared_backend() {
+                Some(backend) => backend,
+                None => &CpuBackend,
+            },
+        }
+    }
+
+    /// Look up a built-in or registered backend by name
+    pub fn named(name: &str) -> Option<Processor> {
+        match name {
+            "cpu" => Some(Processor::CPU),
+            "simd" => Some(Processor::SIMD),
+            "scalar" => Some(Processor::Scalar),
+            #[cfg(feature = "gpu")]
+            "gpu" => Some(Processor::GPU),
+            _ => find_backend(name).map(Processor::Custom),
+        }
+    }
 }
 
 #[derive(Default)]
@@ -29,3 +109,173 @@ pub enum SortBy {
     InstanceNumber,
     None,
 }
+
+#[derive(Clone, Copy, Default)]
+pub enum ProjectionMode {
+    /// Maximum intensity projection (MIP)
+    #[default]
+    Maximum,
+    /// Minimum intensity projection (MinIP)
+    Minimum,
+    /// Average intensity projection
+    Mean,
+    Sum,
+}
+
+/// Target grid for resampling a volume
+#[derive(Clone, Copy)]
+pub enum ResampleTarget {
+    /// Isotropic grid at the smallest spacing of the volume
+    Isotropic,
+    /// Spacing (x, y, z) in mm
+    Spacing((f32, f32, f32)),
+    /// Dimensions (depth, height, width) covering the same extent
+    Shape((usize, usize, usize)),
+}
+
+/// Shading mode of the volume renderer
+#[derive(Clone, Copy, Default)]
+pub enum RenderMode {
+    /// Front-to-back compositing of colour and opacity
+    #[default]
+    Composite,
+    /// Maximum intensity along each ray
+    Maximum,
+    /// First crossing of the threshold, shaded with a headlight
+    IsoSurface(f32),
+}
+
+/// How labels are drawn over a slice
+#[derive(Clone, Copy, Debug, Default)]
+pub enum OverlayStyle {
+    /// Semi-transparent regions
+    Fill,
+    /// Opaque region boundaries
+    Outline,
+    #[default]
+    FillAndOutline,
+}
+
+/// Smoothing filter applied to a volume
+///
+/// Sizes are given in mm and converted per axis through the voxel spacing.
+/// Non-positive sigmas, conductances or time steps leave the volume
+/// unchanged.
+#[derive(Clone, Copy, Debug)]
+pub enum Filter {
+    /// Separable Gaussian
+    Gaussian { sigma: f32 },
+    /// Mean over a box of `radius` mm around each voxel
+    Mean { radius: f32 },
+    /// Median over a box of `radius` mm around each voxel
+    Median { radius: f32 },
+    /// Edge-preserving Gaussian weighted by the intensity difference
+    Bilateral {
+        spatial_sigma: f32,
+        range_sigma: f32,
+    },
+    /// Edge-preserving Perona-Malik diffusion
+    ///
+    /// Gradients well below `conductance` (intensity per mm) are smoothed,
+    /// gradients well above are kept. The time step is limited to the stable
+    /// range of min spacing² / 6.
+    AnisotropicDiffusion {
+        iterations: usize,
+        conductance: f32,
+        time_step: f32,
+    },
+}
+
+/// Finite difference operator for gradients
+#[derive(Clone, Copy, Debug, Default)]
+pub enum GradientOperator {
+    /// Central differences between the two neighbours
+    #[default]
+    CentralDifference,
+    /// Central differences smoothed with [1, 2, 1] across the other axes
+    Sobel,
+}
+
+/// Neighbourhood of a voxel in 3D
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Connectivity {
+    /// 6 neighbours sharing a face
+    Face,
+    /// 18 neighbours sharing a face or an edge
+    Edge,
+    /// 26 neighbours sharing a face, an edge or a corner
+    #[default]
+    Vertex,
+}
+
+impl Connectivity {
+    /// Get the (depth, height, width) offsets of the neighbours
+    pub(crate) fn offsets(&self) -> Vec<[isize; 3]> {
+        let max_distance = match self {
+            Connectivity::Face => 1,
+            Connectivity::Edge => 2,
+            Connectivity::Vertex => 3,
+        };
+        let range = || -1..=1isize;
+        range()
+            .flat_map(|z| range().flat_map(move |y| range().map(move |x| [z, y, x])))
+            .filter(|offset| {
+                let distance = offset.iter().filter(|&&o| o != 0).count();
+                (1..=max_distance).contains(&distance)
+            })
+            .collect()
+    }
+}
+
+/// Condition for voxels to join a growing region
+#[derive(Clone, Copy, Debug)]
+pub enum GrowingCriterion {
+    /// Intensity within `lower..=upper`
+    Interval { lower: f32, upper: f32 },
+    /// Intensity within mean ± `multiplier` · std
+    ///
+    /// The statistics are first taken from the seeds and their direct
+    /// neighbours, then re-estimated from the grown region `iterations` times.
+    ConfidenceConnected { multiplier: f32, iterations: usize },
+    /// All voxels within a box of `radius` mm have an intensity within
+    /// `lower..=upper`
+    Neighborhood { lower: f32, upper: f32, radius: f32 },
+}
+
+/// Shape of the neighbourhood used by binary morphology
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum StructuringElement {
+    /// Voxels whose centres lie within the radius in mm
+    #[default]
+    Sphere,
+    /// Voxels within the radius in mm along each axis
+    Box,
+}
+
+/// Binary morphological operation on a mask volume
+///
+/// Radii are given in mm and converted per axis through the voxel spacing.
+#[derive(Clone, Copy, Debug)]
+pub enum Morphology {
+    Erode {
+        radius: f32,
+        element: StructuringElement,
+    },
+    Dilate {
+        radius: f32,
+        element: StructuringElement,
+    },
+    /// Erosion followed by dilation, removes structures smaller than the
+    /// element
+    Open {
+        radius: f32,
+        element: StructuringElement,
+    },
+    /// Dilation followed by erosion, closes gaps smaller than the element
+    Close {
+        radius: f32,
+        element: StructuringElement,
+    },
+    /// Fill background regions not connected to the border of the volume
+    FillHoles,
+}
@@ -0,0 +1,390 @@
+use crate::enums::Filter;
+use crate::enums::Processor;
+use crate::volume::Volume;
+
+use ndarray::Array3;
+use ndarray::ArrayView3;
+use ndarray::Axis;
+use ndarray::Zip;
+use rayon::iter::IndexedParallelIterator;
+use rayon::iter::IntoParallelIterator;
+use rayon::iter::ParallelIterator;
+
+/// Gaussian kernels are truncated at this many standard deviations
+const GAUSSIAN_TRUNCATE: f32 = 3.0;
+
+/// Get a normalised Gaussian kernel for a standard deviation in voxels
+pub(crate) fn gaussian_kernel(sigma: f32) -> Vec<f32> {
+    let radius = (sigma * GAUSSIAN_TRUNCATE).ceil() as isize;
+    let kernel: Vec<f32> = (-radius..=radius)
+        .map(|i| (-((i * i) as f32) / (2.0 * sigma * sigma)).exp())
+        .collect();
+    let sum: f32 = kernel.iter().sum();
+    kernel.into_iter().map(|weight| weight / sum).collect()
+}
+
+/// Get a voxel of a volume, repeating the border voxels
+#[inline]
+fn clamped(data: &ArrayView3<'_, f32>, index: [usize; 3], offset: [isize; 3]) -> f32 {
+    let dim = data.shape();
+    let at = |axis: usize| {
+        (index[axis] as isize + offset[axis]).clamp(0, dim[axis] as isize - 1) as usize
+    };
+    data[[at(0), at(1), at(2)]]
+}
+
+/// Get all offsets of a box with the given radius per axis
+pub(crate) fn box_offsets(radius: [usize; 3]) -> Vec<[isize; 3]> {
+    let range = |axis: usize| -(radius[axis] as isize)..=radius[axis] as isize;
+    range(0)
+        .flat_map(|z| range(1).flat_map(move |y| range(2).map(move |x| [z, y, x])))
+        .collect()
+}
+
+/// Fill a volume of the given shape in parallel over slices, reusing one
+/// buffer per slice
+fn fill_slices<F>(shape: &[usize], f: F) -> Array3<f32>
+where
+    F: Fn([usize; 3], &mut Vec<f32>) -> f32 + Sync,
+{
+    let mut result = Array3::zeros((shape[0], shape[1], shape[2]));
+    result
+        .axis_iter_mut(Axis(0))
+        .into_par_iter()
+        .enumerate()
+        .for_each(|(z, mut slice)| {
+            let mut buffer = Vec::new();
+            for ((y, x), value) in slice.indexed_iter_mut() {
+                *value = f([z, y, x], &mut buffer);
+            }
+        });
+    result
+}
+
+impl Volume {
+    /// Get the spacing per array axis (depth, height, width)
+    pub(crate) fn axis_spacing(&self) -> [f32; 3] {
+        [self.spacing.2, self.spacing.1, self.spacing.0]
+    }
+
+    /// Get the number of voxels per array axis whose centres lie within a
+    /// distance in mm
+    ///
+    /// Non-finite radii, e.g. of an infinite distance or a zero spacing, are
+    /// 0 so that kernels stay bounded.
+    pub(crate) fn voxel_radius(&self, mm: f32) -> [usize; 3] {
+        self.axis_spacing().map(|spacing| {
+            let radius = mm / spacing + 1e-3;
+            if radius.is_finite() {
+                radius.floor().max(0.0) as usize
+            } else {
+                0
+            }
+        })
+    }
+
+    /// Get a new volume with the filter applied, keeping the geometry
+    ///
+    /// Gaussian and mean filters run on the backend of `processor`, the
+    /// other filters always run on the CPU.
+    pub fn filter(&self, filter: &Filter, processor: &Processor) -> Volume {
+        Volume::new(self.filtered_data(filter, processor), self.spacing)
+            .with_geometry(self.origin, self.direction)
+    }
+
+    /// Apply a filter to the data of the volume, see [`Volume::filter`]
+    pub fn filter_in_place(&mut self, filter: &Filter, processor: &Processor) {
+        let data = self.filtered_data(filter, processor);
+        *self.data_mut() = data;
+    }
+
+    fn filtered_data(&self, filter: &Filter, processor: &Processor) -> Array3<f32> {
+        if self.data.is_empty() {
+            return self.data.clone();
+        }
+
+        match *filter {
+            Filter::Gaussian { sigma } => {
+                let kernels = self.axis_spacing().map(|spacing| {
+                    let sigma = sigma / spacing;
+                    (sigma > 1e-3 && sigma.is_finite()).then(|| gaussian_kernel(sigma))
+                });
+                self.convolve_separable(&kernels, processor)
+            }
+            Filter::Mean { radius } => {
+                let kernels = self.voxel_radius(radius).map(|radius| {
+                    let len = 2 * radius + 1;
+                    (radius > 0).then(|| vec![1.0 / len as f32; len])
+                });
+                self.convolve_separable(&kernels, processor)
+            }
+            Filter::Median { radius } => self.median(self.voxel_radius(radius)),
+            Filter::Bilateral {
+                spatial_sigma,
+                range_sigma,
+            } => self.bilateral(spatial_sigma, range_sigma),
+            Filter::AnisotropicDiffusion {
+                iterations,
+                conductance,
+                time_step,
+            } => self.anisotropic_diffusion(iterations, conductance, time_step),
+        }
+    }
+
+    /// Convolve with one optional kernel per array axis
+    pub(crate) fn convolve_separable(
+        &self,
+        kernels: &[Option<Vec<f32>>; 3],
+        processor: &Processor,
+    ) -> Array3<f32> {
+        let backend = processor.backend();
+        let mut data = self.data.clone();
+        for (axis, kernel) in kernels.iter().enumerate() {
+            if let Some(kernel) = kernel {
+                data = backend.convolve(&data.view(), axis, kernel);
+            }
+        }
+        data
+    }
+
+    fn median(&self, radius: [usize; 3]) -> Array3<f32> {
+        let offsets = box_offsets(radius);
+        let data = self.data.view();
+        fill_slices(data.shape(), |index, buffer| {
+            buffer.clear();
+            buffer.extend(offsets.iter().map(|&offset| clamped(&data, index, offset)));
+            let middle = buffer.len() / 2;
+            *buffer.select_nth_unstable_by(middle, f32::total_cmp).1
+        })
+    }
+
+    fn bilateral(&self, spatial_sigma: f32, range_sigma: f32) -> Array3<f32> {
+        if !(spatial_sigma > 1e-3 && spatial_sigma.is_finite() && range_sigma > 1e-3) {
+            return self.data.clone();
+        }
+        let spacing = self.axis_spacing();
+        let offsets = box_offsets(self.voxel_radius(spatial_sigma * 2.0));
+        let spatial_weights: Vec<f32> = offsets
+            .iter()
+            .map(|offset| {
+                let distance: f32 = (0..3)
+                    .map(|axis| (offset[axis] as f32 * spacing[axis]).powi(2))
+                    .sum();
+                (-distance / (2.0 * spatial_sigma * spatial_sigma)).exp()
+            })
+            .collect();
+        let range_factor = -1.0 / (2.0 * range_sigma * range_sigma);
+
+        let data = self.data.view();
+        fill_slices(data.shape(), |index, _| {
+            let center = data[index];
+            let (sum, weights) = offsets.iter().zip(&spatial_weights).fold(
+                (0.0, 0.0),
+                |(sum, weights), (&offset, &spatial)| {
+                    let value = clamped(&data, index, offset);
+                    let weight = spatial * ((value - center).powi(2) * range_factor).exp();
+                    (sum + value * weight, weights + weight)
+                },
+            );
+            sum / weights
+        })
+    }
+
+    fn anisotropic_diffusion(
+        &self,
+        iterations: usize,
+        conductance: f32,
+        time_step: f32,
+    ) -> Array3<f32> {
+        if !(conductance > 0.0 && time_step > 0.0) {
+            return self.data.clone();
+        }
+        let spacing = self.axis_spacing();
+        let min_spacing = spacing.iter().copied().fold(f32::INFINITY, f32::min);
+        let time_step = time_step.min(min_spacing * min_spacing / 6.0);
+        let flux = |difference: f32, spacing: f32| {
+            let gradient = difference / spacing;
+            (-(gradient / conductance).powi(2)).exp() * gradient / spacing
+        };
+
+        let mut data = self.data.clone();
+        let mut next = Array3::zeros(data.raw_dim());
+        for _ in 0..iterations {
+            let current = data.view();
+            Zip::indexed(&mut next).par_for_each(|(z, y, x), value| {
+                let index = [z, y, x];
+                let center = current[index];
+                let change: f32 = (0..3)
+                    .map(|axis| {
+                        let mut offset = [0; 3];
+                        offset[axis] = 1;
+                        let forward = clamped(&current, index, offset) - center;
+                        offset[axis] = -1;
+                        let backward = clamped(&current, index, offset) - center;
+                        flux(forward, spacing[axis]) + flux(backward, spacing[axis])
+                    })
+                    .sum();
+                *value = center + time_step * change;
+            });
+            std::mem::swap(&mut data, &mut next);
+        }
+        data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::s;
+
+    /// Single bright voxel in the centre of 1 × 1 × 2 mm voxels
+    fn impulse() -> Volume {
+        let mut data = Array3::zeros((9, 13, 13));
+        data[[4, 6, 6]] = 1.0;
+        Volume::new(data, (1.0, 1.0, 2.0))
+    }
+
+    /// Step from 0 to 100 along x with noise of ±1
+    fn step_edge() -> Volume {
+        let data = Array3::from_shape_fn((4, 4, 8), |(z, y, x)| {
+            let noise = if (x + y + z) % 2 == 0 { 1.0 } else { -1.0 };
+            if x < 4 { noise } else { 100.0 + noise }
+        });
+        Volume::new(data, (1.0, 1.0, 1.0))
+    }
+
+    #[test]
+    fn test_gaussian_respects_spacing() {
+        let volume = impulse();
+
+        let result = volume.filter(&Filter::Gaussian { sigma: 2.0 }, &Processor::CPU);
+
+        assert!((result.data.sum() - 1.0).abs() < 1e-4);
+        // 2 mm away along x and along z
+        let along_x = result.data[[4, 6, 8]];
+        let along_z = result.data[[5, 6, 6]];
+        assert!((along_x - along_z).abs() < 1e-4);
+        assert!(along_x > 0.0);
+    }
+
+    #[test]
+    fn test_mean_and_median() {
+        let volume = impulse().filter(&Filter::Mean { radius: 1.0 }, &Processor::Scalar);
+        let mut noisy = Volume::new(Array3::from_elem((5, 5, 5), 10.0), (1.0, 1.0, 1.0));
+        noisy.data_mut()[[2, 2, 2]] = 1000.0;
+
+        let median = noisy.filter(&Filter::Median { radius: 1.0 }, &Processor::CPU);
+
+        // The radius covers a single voxel along z at 2 mm spacing
+        assert!((volume.data[[4, 6, 6]] - 1.0 / 9.0).abs() < 1e-6);
+        assert_eq!(volume.data[[5, 6, 6]], 0.0);
+        assert!(median.data.iter().all(|&v| v == 10.0));
+    }
+
+    #[test]
+    fn test_edge_preserving_filters() {
+        let volume = step_edge();
+        let bilateral = volume.filter(
+            &Filter::Bilateral {
+                spatial_sigma: 1.0,
+                range_sigma: 10.0,
+            },
+            &Processor::CPU,
+        );
+        let diffusion = volume.filter(
+            &Filter::AnisotropicDiffusion {
+                iterations: 20,
+                conductance: 10.0,
+                time_step: 0.15,
+            },
+            &Processor::CPU,
+        );
+        let gaussian = volume.filter(&Filter::Gaussian { sigma: 1.0 }, &Processor::CPU);
+
+        for result in [&bilateral, &diffusion] {
+            let low = result.data.slice(s![.., .., ..4]);
+            let high = result.data.slice(s![.., .., 4..]);
+            assert!(low.iter().all(|&v| v.abs() < 1.0));
+            assert!(high.iter().all(|&v| (v - 100.0).abs() < 1.0));
+        }
+        assert!(gaussian.data[[1, 1, 3]] > 10.0);
+    }
+
+    #[test]
+    fn test_non_positive_parameters_keep_volume() {
+        let volume = step_edge();
+
+        for filter in [
+            Filter::Bilateral {
+                spatial_sigma: 0.0,
+                range_sigma: 10.0,
+            },
+            Filter::Bilateral {
+                spatial_sigma: 1.0,
+                range_sigma: 0.0,
+            },
+            Filter::AnisotropicDiffusion {
+                iterations: 5,
+                conductance: 0.0,
+                time_step: 0.15,
+            },
+            Filter::AnisotropicDiffusion {
+                iterations: 5,
+                conductance: 10.0,
+                time_step: -0.15,
+            },
+            Filter::Gaussian { sigma: 0.0 },
+        ] {
+            let result = volume.filter(&filter, &Processor::CPU);
+            assert_eq!(result.data, volume.data, "{filter:?}");
+        }
+    }
+
+    #[test]
+    fn test_non_finite_radii_keep_volume() {
+        let volume = step_edge();
+        let flat = Volume::new(volume.data.clone(), (1.0, 1.0, 0.0));
+
+        for filter in [
+            Filter::Gaussian {
+                sigma: f32::INFINITY,
+            },
+            Filter::Mean {
+                radius: f32::INFINITY,
+            },
+            Filter::Median {
+                radius: f32::INFINITY,
+            },
+            Filter::Bilateral {
+                spatial_sigma: f32::INFINITY,
+                range_sigma: 10.0,
+            },
+        ] {
+            let result = volume.filter(&filter, &Processor::CPU);
+            assert_eq!(result.data, volume.data, "{filter:?}");
+        }
+        // Kernels along the axis without spacing are skipped
+        for filter in [
+            Filter::Gaussian { sigma: 1.0 },
+            Filter::Mean { radius: 1.0 },
+        ] {
+            let expected =
+                Volume::new(volume.data.clone(), (1.0, 1.0, 1e9)).filter(&filter, &Processor::CPU);
+            let result = flat.filter(&filter, &Processor::CPU);
+            assert_eq!(result.data, expected.data, "{filter:?}");
+        }
+    }
+
+    #[test]
+    fn test_filter_in_place_clears_statistics() {
+        let mut volume = step_edge();
+        let filter = Filter::Mean { radius: 1.0 };
+        let expected = volume.filter(&filter, &Processor::CPU);
+        assert_eq!(volume.statistics().unwrap().max, 101.0);
+
+        volume.filter_in_place(&filter, &Processor::CPU);
+
+        assert_eq!(volume.data, expected.data);
+        assert!(volume.statistics().unwrap().max < 101.0);
+    }
+}
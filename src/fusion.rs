@@ -0,0 +1,175 @@
+use crate::backend::PlaneGrid;
+use crate::colormap::ColorMapping;
+use crate::enums::Interpolation;
+use crate::enums::Orientation;
+use crate::geometry::Point3;
+use crate::interpolator::within_bounds;
+use crate::volume::Volume;
+
+use image::ImageBuffer;
+use image::Rgb;
+use ndarray::Zip;
+
+impl Volume {
+    /// Map a grid in voxel coordinates of this volume to the voxel
+    /// coordinates of another volume through patient space
+    fn map_grid(&self, grid: &PlaneGrid, other: &Volume) -> PlaneGrid {
+        let map = |voxel: Point3| other.patient_to_voxel(self.voxel_to_patient(voxel));
+        let origin = map(grid.origin);
+        PlaneGrid {
+            origin,
+            row_step: map(grid.origin + grid.row_step) - origin,
+            col_step: map(grid.origin + grid.col_step) - origin,
+            dim: grid.dim,
+        }
+    }
+
+    /// Whether continuous voxel coordinates lie within the volume
+    fn contains_voxel(&self, voxel: Point3) -> bool {
+        let (depth, height, width) = self.dim();
+        within_bounds(voxel.x, width)
+            && within_bounds(voxel.y, height)
+            && within_bounds(voxel.z, depth)
+    }
+
+    /// Get a slice at a fractional index blended with a secondary volume,
+    /// e.g. PET over CT
+    ///
+    /// The secondary volume is resampled into the slice plane through patient
+    /// coordinates, so it may have a different spacing and geometry. Where it
+    /// covers the slice, its colour is blended over the base colour with the
+    /// opacity of `secondary_mapping`.
+    pub fn get_fused_image_at_position(
+        &self,
+        position: f32,
+        orientation: Orientation,
+        interpolation: Interpolation,
+        mapping: &ColorMapping,
+        secondary: &Volume,
+        secondary_mapping: &ColorMapping,
+    ) -> Option<ImageBuffer<Rgb<u8>, Vec<u8>>> {
+        let grid = self.get_plane_grid(position, orientation, &interpolation)?;
+        let backend = interpolation.processor().backend();
+        let base = backend.resample_plane(&self.samples(&interpolation), &grid, &interpolation);
+        if secondary.data.is_empty() {
+            return mapping.to_rgb_image(&base.view());
+        }
+
+        let secondary_grid = self.map_grid(&grid, secondary);
+        let overlay = backend.resample_plane(
+            &secondary.samples(&interpolation),
+            &secondary_grid,
+            &interpolation,
+        );
+
+        let base_color = mapping.mapper();
+        let overlay_color = secondary_mapping.mapper();
+        let opacity = secondary_mapping.opacity.clamp(0.0, 1.0);
+        let mut pixels = Vec::with_capacity(base.len() * 3);
+        Zip::indexed(&base)
+            .and(&overlay)
+            .for_each(|(row, col), &base_value, &overlay_value| {
+                let color = base_color(base_value);
+                if !secondary.contains_voxel(secondary_grid.position(row, col)) {
+                    pixels.extend(color);
+                    return;
+                }
+                let overlay = overlay_color(overlay_value);
+                pixels.extend(std::array::from_fn::<_, 3, _>(|c| {
+                    (color[c] as f32 * (1.0 - opacity) + overlay[c] as f32 * opacity).round() as u8
+                }));
+            });
+
+        let (height, width) = grid.dim;
+        ImageBuffer::from_raw(width as u32, height as u32, pixels)
+    }
+
+    /// Get a slice blended with a secondary volume, see
+    /// [`Volume::get_fused_image_at_position`]
+    pub fn get_fused_image_from_axis(
+        &self,
+        index: usize,
+        orientation: Orientation,
+        interpolation: Interpolation,
+        mapping: &ColorMapping,
+        secondary: &Volume,
+        secondary_mapping: &ColorMapping,
+    ) -> Option<ImageBuffer<Rgb<u8>, Vec<u8>>> {
+        self.get_fused_image_at_position(
+            index as f32,
+            orientation,
+            interpolation,
+            mapping,
+            secondary,
+            secondary_mapping,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::colormap::Colormap;
+    use crate::enums::Processor;
+    use crate::geometry::IDENTITY_DIRECTION;
+    use ndarray::Array3;
+
+    /// 10×10×10 mm base volume at 1 mm spacing with constant intensity
+    fn base_volume() -> Volume {
+        Volume::new(Array3::from_elem((11, 11, 11), 0.0), (1.0, 1.0, 1.0))
+    }
+
+    /// Secondary volume at 2 mm spacing covering x >= 4 mm, intensity equals x in mm
+    fn secondary_volume() -> Volume {
+        let data = Array3::from_shape_fn((6, 6, 4), |(_, _, x)| 4.0 + x as f32 * 2.0);
+        Volume::new(data, (2.0, 2.0, 2.0))
+            .with_geometry(Point3::new(4.0, 0.0, 0.0), IDENTITY_DIRECTION)
+    }
+
+    #[test]
+    fn test_secondary_is_resampled_in_patient_space() {
+        let base = base_volume();
+        let secondary = secondary_volume();
+        let overlay = ColorMapping::new(Colormap::Grayscale).with_window(0.0, 10.0);
+
+        let image = base
+            .get_fused_image_from_axis(
+                5,
+                Orientation::Axial,
+                Interpolation::Trilinear(Processor::CPU),
+                &ColorMapping::default(),
+                &secondary,
+                &overlay,
+            )
+            .unwrap();
+
+        assert_eq!(image.dimensions(), (11, 11));
+        // Outside of the secondary volume only the black base is visible
+        assert_eq!(image.get_pixel(3, 5).0, [0; 3]);
+        // x = 5 mm lies between two secondary voxels
+        assert_eq!(image.get_pixel(5, 5).0, [127; 3]);
+        assert_eq!(image.get_pixel(10, 5).0, [255; 3]);
+    }
+
+    #[test]
+    fn test_secondary_opacity() {
+        let base = base_volume();
+        let secondary = secondary_volume();
+        let overlay = ColorMapping::new(Colormap::Grayscale)
+            .with_window(0.0, 10.0)
+            .with_opacity(0.5);
+
+        let image = base
+            .get_fused_image_from_axis(
+                5,
+                Orientation::Coronal,
+                Interpolation::Trilinear(Processor::CPU),
+                &ColorMapping::default(),
+                &secondary,
+                &overlay,
+            )
+            .unwrap();
+
+        assert_eq!(image.get_pixel(10, 5).0, [128; 3]);
+    }
+}
@@ -0,0 +1,125 @@
+use serde::{Deserialize, Serialize};
+use std::ops::{Add, Mul, Neg, Sub};
+
+/// A point or direction in 3D space.
+///
+/// Depending on the context the components are either continuous voxel
+/// coordinates (x = column, y = row, z = slice) or patient coordinates in mm.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Point3 {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+}
+
+impl Point3 {
+    pub const fn new(x: f32, y: f32, z: f32) -> Self {
+        Self { x, y, z }
+    }
+
+    #[inline]
+    pub fn dot(&self, other: &Point3) -> f32 {
+        self.x
+            .mul_add(other.x, self.y.mul_add(other.y, self.z * other.z))
+    }
+
+    #[inline]
+    pub fn cross(&self, other: &Point3) -> Point3 {
+        Point3::new(
+            self.y * other.z - self.z * other.y,
+            self.z * other.x - self.x * other.z,
+            self.x * other.y - self.y * other.x,
+        )
+    }
+
+    #[inline]
+    pub fn length(&self) -> f32 {
+        self.dot(self).sqrt()
+    }
+
+    /// Get the Euclidean distance to another point
+    #[inline]
+    pub fn distance(&self, other: &Point3) -> f32 {
+        (*self - *other).length()
+    }
+
+    /// Get the unit vector pointing in the same direction, or `None` for a zero vector
+    pub fn normalize(&self) -> Option<Point3> {
+        let length = self.length();
+        if length <= f32::EPSILON {
+            return None;
+        }
+        Some(*self * (1.0 / length))
+    }
+
+    /// Multiply component-wise with a (x, y, z) tuple, e.g. a voxel spacing
+    #[inline]
+    pub fn scale(&self, factors: (f32, f32, f32)) -> Point3 {
+        Point3::new(self.x * factors.0, self.y * factors.1, self.z * factors.2)
+    }
+}
+
+impl Add for Point3 {
+    type Output = Point3;
+
+    fn add(self, rhs: Point3) -> Point3 {
+        Point3::new(self.x + rhs.x, self.y + rhs.y, self.z + rhs.z)
+    }
+}
+
+impl Sub for Point3 {
+    type Output = Point3;
+
+    fn sub(self, rhs: Point3) -> Point3 {
+        Point3::new(self.x - rhs.x, self.y - rhs.y, self.z - rhs.z)
+    }
+}
+
+impl Mul<f32> for Point3 {
+    type Output = Point3;
+
+    fn mul(self, rhs: f32) -> Point3 {
+        Point3::new(self.x * rhs, self.y * rhs, self.z * rhs)
+    }
+}
+
+impl Neg for Point3 {
+    type Output = Point3;
+
+    fn neg(self) -> Point3 {
+        Point3::new(-self.x, -self.y, -self.z)
+    }
+}
+
+/// Unit vectors of the voxel x, y and z axes expressed in patient coordinates
+pub const IDENTITY_DIRECTION: [Point3; 3] = [
+    Point3::new(1.0, 0.0, 0.0),
+    Point3::new(0.0, 1.0, 0.0),
+    Point3::new(0.0, 0.0, 1.0),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cross_of_axes() {
+        let x = Point3::new(1.0, 0.0, 0.0);
+        let y = Point3::new(0.0, 1.0, 0.0);
+
+        assert_eq!(x.cross(&y), Point3::new(0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn test_distance() {
+        let a = Point3::new(1.0, 2.0, 3.0);
+        let b = Point3::new(4.0, 6.0, 3.0);
+
+        assert_eq!(a.distance(&b), 5.0);
+    }
+
+    #[test]
+    fn test_normalize_zero_vector() {
+        assert_eq!(Point3::default().normalize(), None);
+    }
+}
@@ -0,0 +1,459 @@
+//! GPU backend using wgpu compute shaders.
+//!
+//! Plane resampling, the stretching of slices and projections run on the
+//! GPU for nearest neighbour, bilinear and trilinear interpolation. The
+//! volume is uploaded for every call. Other operations and interpolations,
+//! inputs or outputs exceeding the buffer limits of the device and failed
+//! GPU operations are computed by [`CpuBackend`].
+//!
+//! Without a hardware adapter, wgpu's software fallback adapter is used if
+//! available.
+
+use crate::backend::ComputeBackend;
+use crate::backend::CpuBackend;
+use crate::backend::PlaneGrid;
+use crate::backend::plane_sampling;
+use crate::enums::Interpolation;
+use crate::enums::ProjectionMode;
+use crate::geometry::Point3;
+
+use ndarray::Array2;
+use ndarray::ArrayView2;
+use ndarray::ArrayView3;
+use ndarray::Axis;
+use std::sync::OnceLock;
+use thiserror::Error;
+use wgpu::util::DeviceExt;
+
+const WORKGROUP_SIZE: u32 = 8;
+
+#[derive(Debug, Error)]
+pub enum GpuError {
+    #[error("No GPU adapter with compute shader support found")]
+    NoAdapter,
+    #[error("Failed to request GPU device")]
+    Device(#[from] wgpu::RequestDeviceError),
+    #[error("Failed to wait for the GPU")]
+    Poll(#[from] wgpu::PollError),
+    #[error("Failed to read back the GPU results")]
+    Map(#[from] wgpu::BufferAsyncError),
+}
+
+/// Backend running on a wgpu device
+pub struct GpuBackend {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    adapter_info: wgpu::AdapterInfo,
+    resample_pipeline: wgpu::ComputePipeline,
+    project_pipeline: wgpu::ComputePipeline,
+}
+
+/// Uniform parameters of the shaders, see `shaders/volume.wgsl`
+#[derive(Default)]
+struct Params {
+    origin: Point3,
+    row_step: Point3,
+    col_step: Point3,
+    /// depth, height, width of the volume
+    volume_dim: (usize, usize, usize),
+    /// output height, output width, interpolation or axis, projection mode
+    options: [u32; 4],
+}
+
+impl Params {
+    fn to_words(&self) -> [u32; 20] {
+        let mut words = [0; 20];
+        for (i, point) in [self.origin, self.row_step, self.col_step]
+            .iter()
+            .enumerate()
+        {
+            words[i * 4] = point.x.to_bits();
+            words[i * 4 + 1] = point.y.to_bits();
+            words[i * 4 + 2] = point.z.to_bits();
+        }
+        words[12] = self.volume_dim.0 as u32;
+        words[13] = self.volume_dim.1 as u32;
+        words[14] = self.volume_dim.2 as u32;
+        words[16..].copy_from_slice(&self.options);
+        words
+    }
+}
+
+impl GpuBackend {
+    /// Create a backend on the preferred adapter, falling back to the
+    /// software adapter
+    pub fn new() -> Result<Self, GpuError> {
+        Self::with_adapter(false).or_else(|_| Self::with_adapter(true))
+    }
+
+    /// Create a backend on wgpu's software fallback adapter
+    pub fn with_fallback_adapter() -> Result<Self, GpuError> {
+        Self::with_adapter(true)
+    }
+
+    fn with_adapter(force_fallback_adapter: bool) -> Result<Self, GpuError> {
+        let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor::from_env_or_default());
+        let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            force_fallback_adapter,
+            compatible_surface: None,
+        }))
+        .map_err(|_| GpuError::NoAdapter)?;
+        if !adapter
+            .get_downlevel_capabilities()
+            .flags
+            .contains(wgpu::DownlevelFlags::COMPUTE_SHADERS)
+        {
+            return Err(GpuError::NoAdapter);
+        }
+
+        let (device, queue) =
+            pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor {
+                label: Some("dicom-volume"),
+                required_features: wgpu::Features::empty(),
+                required_limits: adapter.limits(),
+                memory_hints: wgpu::MemoryHints::Performance,
+                trace: wgpu::Trace::Off,
+            }))?;
+
+        let module = device.create_shader_module(wgpu::include_wgsl!("shaders/volume.wgsl"));
+        let pipeline = |entry_point| {
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some(entry_point),
+                layout: None,
+                module: &module,
+                entry_point: Some(entry_point),
+                compilation_options: Default::default(),
+                cache: None,
+            })
+        };
+        let resample_pipeline = pipeline("resample");
+        let project_pipeline = pipeline("project");
+
+        Ok(Self {
+            adapter_info: adapter.get_info(),
+            device,
+            queue,
+            resample_pipeline,
+            project_pipeline,
+        })
+    }
+
+    /// Name of the adapter the backend runs on
+    pub fn adapter_name(&self) -> &str {
+        &self.adapter_info.name
+    }
+
+    /// Whether the volume and an output of (height, width) each fit into a
+    /// single storage buffer and the output into a single dispatch
+    fn fits(&self, volume: &ArrayView3<'_, f32>, dim: (usize, usize)) -> bool {
+        let limits = self.device.limits();
+        let fits_buffer = |len: usize| {
+            let size = (len * size_of::<f32>()) as u64;
+            len > 0
+                && len <= u32::MAX as usize
+                && size <= limits.max_storage_buffer_binding_size as u64
+                && size <= limits.max_buffer_size
+        };
+        let workgroups = |len: usize| len.div_ceil(WORKGROUP_SIZE as usize) as u64;
+        fits_buffer(volume.len())
+            && fits_buffer(dim.0 * dim.1)
+            && workgroups(dim.0).max(workgroups(dim.1))
+                <= limits.max_compute_workgroups_per_dimension as u64
+    }
+
+    /// Run a shader over the output grid of `params` and read back the result
+    fn dispatch(
+        &self,
+        pipeline: &wgpu::ComputePipeline,
+        volume: &ArrayView3<'_, f32>,
+        params: &Params,
+    ) -> Result<Array2<f32>, GpuError> {
+        let (height, width) = (params.options[0], params.options[1]);
+        let source = volume.as_standard_layout();
+        let data = source.as_slice().expect("standard layout");
+        let size = (height as usize * width as usize * size_of::<f32>()) as u64;
+
+        let uniform = self
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("params"),
+                contents: bytemuck::cast_slice(&params.to_words()),
+                usage: wgpu::BufferUsages::UNIFORM,
+            });
+        let input = self
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("volume"),
+                contents: bytemuck::cast_slice(data),
+                usage: wgpu::BufferUsages::STORAGE,
+            });
+        let output = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("output"),
+            size,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let staging = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("staging"),
+            size,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout: &pipeline.get_bind_group_layout(0),
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: uniform.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: input.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: output.as_entire_binding(),
+                },
+            ],
+        });
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor::default());
+            pass.set_pipeline(pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            pass.dispatch_workgroups(
+                width.div_ceil(WORKGROUP_SIZE),
+                height.div_ceil(WORKGROUP_SIZE),
+                1,
+            );
+        }
+        encoder.copy_buffer_to_buffer(&output, 0, &staging, 0, size);
+        self.queue.submit([encoder.finish()]);
+
+        let slice = staging.slice(..);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        self.device.poll(wgpu::PollType::Wait)?;
+        receiver
+            .try_recv()
+            .map_err(|_| GpuError::Map(wgpu::BufferAsyncError))??;
+
+        let values = bytemuck::cast_slice::<u8, f32>(&slice.get_mapped_range()).to_vec();
+        staging.unmap();
+        Ok(
+            Array2::from_shape_vec((height as usize, width as usize), values)
+                .expect("matching shape"),
+        )
+    }
+
+    /// Shader constant of an interpolation, `None` if not supported
+    fn interpolation_mode(interpolation: &Interpolation) -> Option<u32> {
+        match interpolation {
+            Interpolation::None | Interpolation::NearestNeighbor(_) => Some(0),
+            // Planes are snapped for bilinear sampling, see `plane_sampling`
+            Interpolation::Bilinear(_) | Interpolation::Trilinear(_) => Some(1),
+            _ => None,
+        }
+    }
+}
+
+impl ComputeBackend for GpuBackend {
+    fn name(&self) -> &str {
+        "gpu"
+    }
+
+    fn resample_slice(
+        &self,
+        slice: &ArrayView2<'_, f32>,
+        dim: (usize, usize),
+        interpolation: &Interpolation,
+    ) -> Array2<f32> {
+        let volume = slice.view().insert_axis(Axis(0));
+        let (height, width) = slice.dim();
+        if Self::interpolation_mode(interpolation).is_none() || !self.fits(&volume, dim) {
+            return CpuBackend.resample_slice(slice, dim, interpolation);
+        }
+
+        // Map the corner samples onto each other
+        let scale_y = (height - 1) as f32 / (dim.0.max(2) - 1) as f32;
+        let scale_x = (width - 1) as f32 / (dim.1.max(2) - 1) as f32;
+        let grid = PlaneGrid {
+            origin: Point3::default(),
+            row_step: Point3::new(0.0, scale_y, 0.0),
+            col_step: Point3::new(scale_x, 0.0, 0.0),
+            dim,
+        };
+        self.resample_plane(&volume, &grid, interpolation)
+    }
+
+    fn resample_plane(
+        &self,
+        volume: &ArrayView3<'_, f32>,
+        grid: &PlaneGrid,
+        interpolation: &Interpolation,
+    ) -> Array2<f32> {
+        let (grid, interpolation) = plane_sampling(grid, interpolation);
+        let grid = &grid;
+        let Some(mode) = Self::interpolation_mode(&interpolation) else {
+            return CpuBackend.resample_plane(volume, grid, &interpolation);
+        };
+        if !self.fits(volume, grid.dim) {
+            return CpuBackend.resample_plane(volume, grid, &interpolation);
+        }
+
+        let params = Params {
+            origin: grid.origin,
+            row_step: grid.row_step,
+            col_step: grid.col_step,
+            volume_dim: volume.dim(),
+            options: [grid.dim.0 as u32, grid.dim.1 as u32, mode, 0],
+        };
+        self.dispatch(&self.resample_pipeline, volume, &params)
+            .unwrap_or_else(|_| CpuBackend.resample_plane(volume, grid, &interpolation))
+    }
+
+    fn project(
+        &self,
+        volume: &ArrayView3<'_, f32>,
+        axis: usize,
+        mode: ProjectionMode,
+    ) -> Array2<f32> {
+        let mut dim = volume.shape().to_vec();
+        dim.remove(axis);
+        if !self.fits(volume, (dim[0], dim[1])) {
+            return CpuBackend.project(volume, axis, mode);
+        }
+
+        let shader_mode = match mode {
+            ProjectionMode::Maximum => 0,
+            ProjectionMode::Minimum => 1,
+            ProjectionMode::Mean => 2,
+            ProjectionMode::Sum => 3,
+        };
+        let params = Params {
+            volume_dim: volume.dim(),
+            options: [dim[0] as u32, dim[1] as u32, axis as u32, shader_mode],
+            ..Default::default()
+        };
+        self.dispatch(&self.project_pipeline, volume, &params)
+            .unwrap_or_else(|_| CpuBackend.project(volume, axis, mode))
+    }
+}
+
+/// Shared backend used by [`Processor::GPU`], `None` without a usable adapter
+///
+/// [`Processor::GPU`]: crate::enums::Processor::GPU
+pub(crate) fn shared_backend() -> Option<&'static GpuBackend> {
+    static BACKEND: OnceLock<Option<GpuBackend>> = OnceLock::new();
+    BACKEND.get_or_init(|| GpuBackend::new().ok()).as_ref()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::enums::Processor;
+    use ndarray::Array3;
+
+    const TOLERANCE: f32 = 1e-3;
+
+    fn test_data() -> Array3<f32> {
+        Array3::from_shape_fn((9, 13, 21), |(z, y, x)| {
+            ((z * 131 + y * 71 + x * 37) % 97) as f32 * 7.0 - 100.0
+        })
+    }
+
+    fn assert_close(a: &Array2<f32>, b: &Array2<f32>) {
+        assert_eq!(a.dim(), b.dim());
+        for (a, b) in a.iter().zip(b) {
+            assert!((a - b).abs() <= TOLERANCE * b.abs().max(1.0), "{a} != {b}");
+        }
+    }
+
+    /// Backend on the software adapter so that the shaders are tested without
+    /// a GPU, `None` if no adapter exists at all
+    fn backend() -> Option<&'static GpuBackend> {
+        static BACKEND: OnceLock<Option<GpuBackend>> = OnceLock::new();
+        let backend = BACKEND
+            .get_or_init(|| {
+                GpuBackend::with_fallback_adapter()
+                    .or_else(|_| GpuBackend::new())
+                    .ok()
+            })
+            .as_ref();
+        if backend.is_none() {
+            eprintln!("skipped: no wgpu adapter available");
+        }
+        backend
+    }
+
+    #[test]
+    fn test_resample_plane_matches_cpu() {
+        let Some(gpu) = backend() else {
+            return;
+        };
+        let data = test_data();
+        // Oblique plane that partially leaves the volume
+        let grid = PlaneGrid {
+            origin: Point3::new(-2.0, 0.5, 1.25),
+            row_step: Point3::new(0.1, 0.45, 0.2),
+            col_step: Point3::new(0.7, 0.05, 0.15),
+            dim: (31, 37),
+        };
+
+        for interpolation in [
+            Interpolation::NearestNeighbor(Processor::GPU),
+            Interpolation::Bilinear(Processor::GPU),
+            Interpolation::Trilinear(Processor::GPU),
+        ] {
+            assert_close(
+                &gpu.resample_plane(&data.view(), &grid, &interpolation),
+                &CpuBackend.resample_plane(&data.view(), &grid, &interpolation),
+            );
+        }
+    }
+
+    #[test]
+    fn test_resample_slice_matches_cpu() {
+        let Some(gpu) = backend() else {
+            return;
+        };
+        let data = test_data();
+        let slice = data.index_axis(Axis(1), 4);
+        let interpolation = Interpolation::Bilinear(Processor::GPU);
+
+        assert_close(
+            &gpu.resample_slice(&slice, (29, 35), &interpolation),
+            &CpuBackend.resample_slice(&slice, (29, 35), &interpolation),
+        );
+    }
+
+    #[test]
+    fn test_projection_matches_cpu() {
+        let Some(gpu) = backend() else {
+            return;
+        };
+        let data = test_data();
+
+        for axis in 0..3 {
+            for mode in [
+                ProjectionMode::Maximum,
+                ProjectionMode::Minimum,
+                ProjectionMode::Mean,
+                ProjectionMode::Sum,
+            ] {
+                assert_close(
+                    &gpu.project(&data.view(), axis, mode),
+                    &CpuBackend.project(&data.view(), axis, mode),
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,300 @@
+use crate::enums::GradientOperator;
+use crate::enums::Orientation;
+use crate::enums::Processor;
+use crate::filter::gaussian_kernel;
+use crate::volume::Volume;
+
+use ndarray::Array2;
+use ndarray::Array3;
+use ndarray::ArrayView2;
+use ndarray::Axis;
+use ndarray::Zip;
+use std::collections::VecDeque;
+
+/// Smoothing across the derivative axis of the Sobel operator
+const SOBEL_SMOOTHING: [f32; 3] = [0.25, 0.5, 0.25];
+
+/// Gradient of a volume in intensity per mm along the voxel axes
+pub struct Gradient {
+    pub x: Array3<f32>,
+    pub y: Array3<f32>,
+    pub z: Array3<f32>,
+}
+
+impl Gradient {
+    /// Get the length of the gradient vectors
+    pub fn magnitude(&self) -> Array3<f32> {
+        Zip::from(&self.x)
+            .and(&self.y)
+            .and(&self.z)
+            .par_map_collect(|&x, &y, &z| (x * x + y * y + z * z).sqrt())
+    }
+}
+
+/// Get the central difference kernel for a spacing in mm
+fn derivative_kernel(spacing: f32) -> Vec<f32> {
+    vec![-0.5 / spacing, 0.0, 0.5 / spacing]
+}
+
+impl Volume {
+    /// Get the gradient along each axis, scaled by the voxel spacing
+    ///
+    /// Border voxels are repeated outside of the volume.
+    pub fn gradient(&self, operator: GradientOperator, processor: &Processor) -> Gradient {
+        let spacing = self.axis_spacing();
+        let along = |axis: usize| {
+            let kernels: [Option<Vec<f32>>; 3] = std::array::from_fn(|other| {
+                if other == axis {
+                    Some(derivative_kernel(spacing[axis]))
+                } else {
+                    match operator {
+                        GradientOperator::CentralDifference => None,
+                        GradientOperator::Sobel => Some(SOBEL_SMOOTHING.to_vec()),
+                    }
+                }
+            });
+            self.convolve_separable(&kernels, processor)
+        };
+        Gradient {
+            x: along(2),
+            y: along(1),
+            z: along(0),
+        }
+    }
+
+    /// Get a new volume with the gradient magnitude in intensity per mm
+    pub fn gradient_magnitude(&self, operator: GradientOperator, processor: &Processor) -> Volume {
+        Volume::new(self.gradient(operator, processor).magnitude(), self.spacing)
+            .with_geometry(self.origin, self.direction)
+    }
+
+    /// Get a new volume with the Laplacian of the volume smoothed by a
+    /// Gaussian of `sigma` mm
+    pub fn laplacian_of_gaussian(&self, sigma: f32, processor: &Processor) -> Volume {
+        let spacing = self.axis_spacing();
+        let kernels = spacing.map(|spacing| {
+            let sigma = sigma / spacing;
+            (sigma > 1e-3 && sigma.is_finite()).then(|| gaussian_kernel(sigma))
+        });
+        let smoothed = Volume::new(self.convolve_separable(&kernels, processor), self.spacing);
+
+        let backend = processor.backend();
+        let mut laplacian = Array3::zeros(self.data.raw_dim());
+        for (axis, spacing) in spacing.into_iter().enumerate() {
+            let weight = 1.0 / (spacing * spacing);
+            let second = backend.convolve(
+                &smoothed.data.view(),
+                axis,
+                &[weight, -2.0 * weight, weight],
+            );
+            laplacian += &second;
+        }
+        Volume::new(laplacian, self.spacing).with_geometry(self.origin, self.direction)
+    }
+
+    /// Detect edges on a slice with the Canny method
+    ///
+    /// The slice is smoothed by a Gaussian of `sigma` mm. Local maxima of the
+    /// gradient magnitude (intensity per mm) above `high` are edges, as well
+    /// as maxima above `low` connected to them. Returns `None` if the index
+    /// is out of bounds.
+    pub fn get_edges_from_axis(
+        &self,
+        index: usize,
+        orientation: Orientation,
+        sigma: f32,
+        (low, high): (f32, f32),
+        processor: &Processor,
+    ) -> Option<Array2<bool>> {
+        let slice = self.get_slice_from_axis(index, &orientation)?;
+        let (row_spacing, col_spacing) = self.get_pixel_spacing(&orientation);
+        let backend = processor.backend();
+
+        let mut smoothed = slice.insert_axis(Axis(0)).to_owned();
+        for (axis, spacing) in [(1, row_spacing), (2, col_spacing)] {
+            let sigma = sigma / spacing;
+            if sigma > 1e-3 && sigma.is_finite() {
+                smoothed = backend.convolve(&smoothed.view(), axis, &gaussian_kernel(sigma));
+            }
+        }
+        let sobel = |axis: usize, spacing: f32| {
+            let across = 3 - axis;
+            let smoothed = backend.convolve(&smoothed.view(), across, &SOBEL_SMOOTHING);
+            backend
+                .convolve(&smoothed.view(), axis, &derivative_kernel(spacing))
+                .index_axis_move(Axis(0), 0)
+        };
+        let gy = sobel(1, row_spacing);
+        let gx = sobel(2, col_spacing);
+        let magnitude = Zip::from(&gx)
+            .and(&gy)
+            .par_map_collect(|&x, &y| (x * x + y * y).sqrt());
+
+        let candidates = Self::suppress_non_maxima(
+            &magnitude.view(),
+            &gx.view(),
+            &gy.view(),
+            (row_spacing, col_spacing),
+            low,
+        );
+        Some(Self::hysteresis(&candidates, &magnitude.view(), high))
+    }
+
+    /// Keep pixels above `low` whose magnitude is not below that of both
+    /// neighbours along the gradient direction
+    fn suppress_non_maxima(
+        magnitude: &ArrayView2<'_, f32>,
+        gx: &ArrayView2<'_, f32>,
+        gy: &ArrayView2<'_, f32>,
+        (row_spacing, col_spacing): (f32, f32),
+        low: f32,
+    ) -> Array2<bool> {
+        let (height, width) = magnitude.dim();
+        let at = |row: isize, col: isize| {
+            if (0..height as isize).contains(&row) && (0..width as isize).contains(&col) {
+                magnitude[[row as usize, col as usize]]
+            } else {
+                0.0
+            }
+        };
+
+        let mut candidates = Array2::from_elem(magnitude.dim(), false);
+        Zip::indexed(&mut candidates).par_for_each(|(row, col), candidate| {
+            let value = magnitude[[row, col]];
+            if value < low || value == 0.0 {
+                return;
+            }
+            // Direction in pixels, quantised to one of the 8 neighbours
+            let angle = (gy[[row, col]] / row_spacing).atan2(gx[[row, col]] / col_spacing);
+            let sector = ((angle / std::f32::consts::FRAC_PI_4).round() as isize).rem_euclid(4);
+            let (dr, dc) = [(0, 1), (1, 1), (1, 0), (1, -1)][sector as usize];
+            let (row, col) = (row as isize, col as isize);
+            *candidate = value >= at(row + dr, col + dc) && value >= at(row - dr, col - dc);
+        });
+        candidates
+    }
+
+    /// Keep candidates above `high` and candidates 8-connected to them
+    fn hysteresis(
+        candidates: &Array2<bool>,
+        magnitude: &ArrayView2<'_, f32>,
+        high: f32,
+    ) -> Array2<bool> {
+        let (height, width) = candidates.dim();
+        let mut edges = Array2::from_elem(candidates.dim(), false);
+        let mut queue: VecDeque<(usize, usize)> = candidates
+            .indexed_iter()
+            .filter(|&(index, &candidate)| candidate && magnitude[index] >= high)
+            .map(|(index, _)| index)
+            .collect();
+        for &index in &queue {
+            edges[index] = true;
+        }
+
+        while let Some((row, col)) = queue.pop_front() {
+            for dr in -1..=1isize {
+                for dc in -1..=1isize {
+                    let (r, c) = (row as isize + dr, col as isize + dc);
+                    if !(0..height as isize).contains(&r) || !(0..width as isize).contains(&c) {
+                        continue;
+                    }
+                    let index = (r as usize, c as usize);
+                    if candidates[index] && !edges[index] {
+                        edges[index] = true;
+                        queue.push_back(index);
+                    }
+                }
+            }
+        }
+        edges
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Linear ramp of 2 per mm along x and 3 per mm along z at 0.5 × 1 × 2.5 mm
+    fn ramp() -> Volume {
+        let data = Array3::from_shape_fn((5, 5, 5), |(z, _, x)| {
+            2.0 * x as f32 * 0.5 + 3.0 * z as f32 * 2.5
+        });
+        Volume::new(data, (0.5, 1.0, 2.5))
+    }
+
+    #[test]
+    fn test_gradients_are_scaled_by_spacing() {
+        let volume = ramp();
+
+        for operator in [GradientOperator::CentralDifference, GradientOperator::Sobel] {
+            let gradient = volume.gradient(operator, &Processor::CPU);
+
+            assert!((gradient.x[[2, 2, 2]] - 2.0).abs() < 1e-4);
+            assert!(gradient.y[[2, 2, 2]].abs() < 1e-4);
+            assert!((gradient.z[[2, 2, 2]] - 3.0).abs() < 1e-4);
+        }
+        let magnitude = volume.gradient_magnitude(GradientOperator::Sobel, &Processor::Scalar);
+        assert!((magnitude.data[[2, 3, 1]] - 13.0f32.sqrt()).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_laplacian_of_quadratic() {
+        // Intensity equals the squared distance in mm from the centre
+        let data = Array3::from_shape_fn((9, 9, 9), |(z, y, x)| {
+            let (x, y, z) = (x as f32 - 4.0, (y as f32 - 4.0) * 2.0, z as f32 - 4.0);
+            x * x + y * y + z * z
+        });
+        let volume = Volume::new(data, (1.0, 2.0, 1.0));
+
+        let result = volume.laplacian_of_gaussian(0.5, &Processor::CPU);
+
+        assert!((result.data[[4, 4, 4]] - 6.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_infinite_sigma_skips_smoothing() {
+        let volume = ramp();
+
+        let laplacian = volume.laplacian_of_gaussian(f32::INFINITY, &Processor::CPU);
+        let edges = volume.get_edges_from_axis(
+            2,
+            Orientation::Axial,
+            f32::INFINITY,
+            (5.0, 20.0),
+            &Processor::CPU,
+        );
+
+        assert_eq!(
+            laplacian.data,
+            volume.laplacian_of_gaussian(0.0, &Processor::CPU).data
+        );
+        assert_eq!(
+            edges,
+            volume.get_edges_from_axis(2, Orientation::Axial, 0.0, (5.0, 20.0), &Processor::CPU)
+        );
+    }
+
+    #[test]
+    fn test_canny_finds_square_outline() {
+        let data = Array3::from_shape_fn((3, 20, 20), |(_, y, x)| {
+            if (6..14).contains(&y) && (6..14).contains(&x) {
+                100.0
+            } else {
+                0.0
+            }
+        });
+        let volume = Volume::new(data, (1.0, 1.0, 1.0));
+
+        let edges = volume
+            .get_edges_from_axis(1, Orientation::Axial, 1.0, (5.0, 20.0), &Processor::CPU)
+            .unwrap();
+
+        assert!(edges[[10, 6]] || edges[[10, 5]]);
+        assert!(edges[[6, 10]] || edges[[5, 10]]);
+        assert!(!edges[[10, 10]]);
+        assert!(!edges[[1, 1]]);
+        // Edges are thin: at most two pixels across the boundary in each row
+        let row = edges.row(10);
+        assert!(row.iter().take(10).filter(|&&edge| edge).count() <= 2);
+    }
+}
@@ -0,0 +1,436 @@
+use crate::volume::Volume;
+
+use ndarray::Axis;
+use ndarray::Zip;
+use rayon::iter::IntoParallelIterator;
+use rayon::iter::ParallelIterator;
+
+/// Number of bins of the histogram cached in [`VolumeStatistics`]
+const STATISTICS_BINS: usize = 4096;
+
+/// Parameters of a histogram
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HistogramOptions {
+    pub bins: usize,
+    /// Range of intensities covered by the bins, defaults to the range of
+    /// the counted voxels
+    pub range: Option<(f32, f32)>,
+    /// Accumulate the counts of all lower bins in each bin
+    pub cumulative: bool,
+}
+
+impl Default for HistogramOptions {
+    fn default() -> Self {
+        Self::new(256)
+    }
+}
+
+impl HistogramOptions {
+    pub fn new(bins: usize) -> Self {
+        Self {
+            bins,
+            range: None,
+            cumulative: false,
+        }
+    }
+
+    pub fn with_range(mut self, lower: f32, upper: f32) -> Self {
+        self.range = Some((lower, upper));
+        self
+    }
+
+    pub fn with_cumulative(mut self, cumulative: bool) -> Self {
+        self.cumulative = cumulative;
+        self
+    }
+}
+
+/// Histogram of equally wide bins over a range of intensities
+///
+/// Values outside of the range are not counted. The upper end of the range
+/// belongs to the last bin.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Histogram {
+    pub counts: Vec<u64>,
+    pub range: (f32, f32),
+    pub cumulative: bool,
+}
+
+impl Histogram {
+    /// Get the number of counted values
+    pub fn total(&self) -> u64 {
+        if self.cumulative {
+            self.counts.last().copied().unwrap_or(0)
+        } else {
+            self.counts.iter().sum()
+        }
+    }
+
+    pub fn bin_width(&self) -> f32 {
+        (self.range.1 - self.range.0) / self.counts.len() as f32
+    }
+
+    /// Get the intensity at the centre of a bin
+    pub fn bin_center(&self, bin: usize) -> f32 {
+        self.range.0 + (bin as f32 + 0.5) * self.bin_width()
+    }
+
+    /// Estimate a percentile in 0..=100, interpolating linearly within the
+    /// bin it falls into
+    pub fn percentile(&self, percentile: f32) -> f32 {
+        let target = (percentile.clamp(0.0, 100.0) / 100.0) as f64 * self.total() as f64;
+        let mut below = 0u64;
+        for (bin, &count) in self.counts.iter().enumerate() {
+            let cumulative = if self.cumulative {
+                count
+            } else {
+                below + count
+            };
+            if cumulative as f64 >= target && cumulative > below {
+                let t = (target - below as f64) / (cumulative - below) as f64;
+                return self.range.0 + (bin as f32 + t as f32) * self.bin_width();
+            }
+            below = cumulative;
+        }
+        self.range.1
+    }
+}
+
+/// Global intensity statistics of a volume, see [`Volume::statistics`]
+#[derive(Clone, Debug, PartialEq)]
+pub struct VolumeStatistics {
+    pub count: usize,
+    pub min: f32,
+    pub max: f32,
+    pub mean: f32,
+    /// Population standard deviation
+    pub std: f32,
+    /// Number of voxels at the minimum, e.g. padding or clipped air
+    pub min_count: usize,
+    /// Number of voxels at the maximum, e.g. saturated detectors
+    pub max_count: usize,
+    /// Histogram over `min..=max`
+    pub histogram: Histogram,
+}
+
+impl VolumeStatistics {
+    /// Estimate a percentile in 0..=100 from the histogram
+    pub fn percentile(&self, percentile: f32) -> f32 {
+        self.histogram.percentile(percentile)
+    }
+
+    /// Whether all voxels share the same intensity, e.g. an empty series
+    pub fn is_constant(&self) -> bool {
+        self.min == self.max
+    }
+}
+
+impl Volume {
+    /// Get the values of all voxels, or the non-zero voxels of a mask of the
+    /// same dimensions, per slice
+    fn masked_values<'a>(
+        &'a self,
+        mask: Option<&'a Volume>,
+    ) -> Option<impl ParallelIterator<Item = Vec<f32>> + 'a> {
+        if mask.is_some_and(|mask| mask.dim() != self.dim()) {
+            return None;
+        }
+
+        Some((0..self.dim().0).into_par_iter().map(move |z| {
+            let slice = self.data.index_axis(Axis(0), z);
+            match mask {
+                Some(mask) => {
+                    let mut values = Vec::new();
+                    Zip::from(&slice)
+                        .and(mask.data.index_axis(Axis(0), z))
+                        .for_each(|&value, &label| {
+                            if label != 0.0 {
+                                values.push(value);
+                            }
+                        });
+                    values
+                }
+                None => slice.iter().copied().collect(),
+            }
+        }))
+    }
+
+    /// Compute a histogram of the volume, optionally restricted to the
+    /// non-zero voxels of a mask of the same dimensions
+    ///
+    /// Returns `None` if there are no bins, no voxels to count or the mask
+    /// does not match the volume.
+    pub fn histogram(
+        &self,
+        options: &HistogramOptions,
+        mask: Option<&Volume>,
+    ) -> Option<Histogram> {
+        if options.bins == 0 {
+            return None;
+        }
+        let (lower, upper) = match options.range {
+            Some(range) => range,
+            None => self
+                .masked_values(mask)?
+                .map(|values| {
+                    values
+                        .iter()
+                        .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), &v| {
+                            (min.min(v), max.max(v))
+                        })
+                })
+                .reduce(
+                    || (f32::INFINITY, f32::NEG_INFINITY),
+                    |a, b| (a.0.min(b.0), a.1.max(b.1)),
+                ),
+        };
+        if lower.is_nan() || upper.is_nan() || lower > upper {
+            return None;
+        }
+
+        let bins = options.bins;
+        let scale = if upper > lower {
+            bins as f32 / (upper - lower)
+        } else {
+            0.0
+        };
+        let mut counts = self
+            .masked_values(mask)?
+            .map(|values| {
+                let mut counts = vec![0u64; bins];
+                for value in values {
+                    if (lower..=upper).contains(&value) {
+                        let bin = (((value - lower) * scale) as usize).min(bins - 1);
+                        counts[bin] += 1;
+                    }
+                }
+                counts
+            })
+            .reduce(
+                || vec![0u64; bins],
+                |mut a, b| {
+                    a.iter_mut().zip(b).for_each(|(a, b)| *a += b);
+                    a
+                },
+            );
+
+        if options.cumulative {
+            let mut total = 0;
+            for count in &mut counts {
+                total += *count;
+                *count = total;
+            }
+        }
+        Some(Histogram {
+            counts,
+            range: (lower, upper),
+            cumulative: options.cumulative,
+        })
+    }
+
+    /// Get the global intensity statistics, computed once and cached until
+    /// the data is modified through [`Volume::data_mut`]
+    ///
+    /// NaN voxels are ignored. Returns `None` if there are no other voxels.
+    pub fn statistics(&self) -> Option<&VolumeStatistics> {
+        self.statistics
+            .get_or_init(|| self.compute_statistics())
+            .as_ref()
+    }
+
+    fn compute_statistics(&self) -> Option<VolumeStatistics> {
+        let moments = self
+            .masked_values(None)?
+            .map(|values| values.into_iter().fold(Moments::EMPTY, Moments::add))
+            .reduce(|| Moments::EMPTY, Moments::merge);
+        if moments.count == 0 {
+            return None;
+        }
+
+        let histogram = self.histogram(
+            &HistogramOptions::new(STATISTICS_BINS).with_range(moments.min, moments.max),
+            None,
+        )?;
+        Some(VolumeStatistics {
+            count: moments.count,
+            min: moments.min,
+            max: moments.max,
+            mean: moments.mean as f32,
+            std: moments.std(),
+            min_count: moments.min_count,
+            max_count: moments.max_count,
+            histogram,
+        })
+    }
+}
+
+/// Count, mean, sum of squared deviations and extremes of the intensities,
+/// accumulated with Welford's algorithm and merged across slices
+#[derive(Clone, Copy)]
+pub(crate) struct Moments {
+    pub(crate) count: usize,
+    pub(crate) mean: f64,
+    m2: f64,
+    pub(crate) min: f32,
+    pub(crate) max: f32,
+    min_count: usize,
+    max_count: usize,
+}
+
+impl Moments {
+    pub(crate) const EMPTY: Self = Self {
+        count: 0,
+        mean: 0.0,
+        m2: 0.0,
+        min: f32::INFINITY,
+        max: f32::NEG_INFINITY,
+        min_count: 0,
+        max_count: 0,
+    };
+
+    /// Add a value, skipping NaN
+    pub(crate) fn add(self, value: f32) -> Self {
+        if value.is_nan() {
+            return self;
+        }
+        let single = Self {
+            count: 1,
+            mean: value as f64,
+            m2: 0.0,
+            min: value,
+            max: value,
+            min_count: 1,
+            max_count: 1,
+        };
+        self.merge(single)
+    }
+
+    pub(crate) fn merge(self, other: Self) -> Self {
+        if other.count == 0 {
+            return self;
+        }
+        if self.count == 0 {
+            return other;
+        }
+        let count = self.count + other.count;
+        let delta = other.mean - self.mean;
+        let weight = other.count as f64 / count as f64;
+        let extreme = |a: (f32, usize), b: (f32, usize), lower: bool| {
+            if a.0 == b.0 {
+                (a.0, a.1 + b.1)
+            } else if (a.0 < b.0) == lower {
+                a
+            } else {
+                b
+            }
+        };
+        let (min, min_count) = extreme(
+            (self.min, self.min_count),
+            (other.min, other.min_count),
+            true,
+        );
+        let (max, max_count) = extreme(
+            (self.max, self.max_count),
+            (other.max, other.max_count),
+            false,
+        );
+        Self {
+            count,
+            mean: self.mean + delta * weight,
+            m2: self.m2 + other.m2 + delta * delta * self.count as f64 * weight,
+            min,
+            max,
+            min_count,
+            max_count,
+        }
+    }
+
+    /// Population standard deviation
+    pub(crate) fn std(&self) -> f32 {
+        (self.m2 / self.count as f64).sqrt() as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::Array3;
+    use ndarray::s;
+
+    /// Intensity equals the column index 0..10
+    fn test_volume() -> Volume {
+        let data = Array3::from_shape_fn((2, 5, 10), |(_, _, x)| x as f32);
+        Volume::new(data, (1.0, 1.0, 1.0))
+    }
+
+    #[test]
+    fn test_histogram_bins_and_range() {
+        let volume = test_volume();
+
+        let histogram = volume.histogram(&HistogramOptions::new(5), None).unwrap();
+        let clipped = volume
+            .histogram(&HistogramOptions::new(2).with_range(2.0, 5.0), None)
+            .unwrap();
+
+        assert_eq!(histogram.range, (0.0, 9.0));
+        assert_eq!(histogram.counts, vec![20, 20, 20, 20, 20]);
+        assert_eq!(clipped.counts, vec![20, 20]);
+        assert_eq!(clipped.bin_center(1), 4.25);
+    }
+
+    #[test]
+    fn test_masked_cumulative_histogram() {
+        let volume = test_volume();
+        let mut mask = Volume::new(Array3::zeros((2, 5, 10)), (1.0, 1.0, 1.0));
+        mask.data_mut().slice_mut(s![0, 0, 6..]).fill(1.0);
+
+        let histogram = volume
+            .histogram(
+                &HistogramOptions::new(4)
+                    .with_range(0.0, 8.0)
+                    .with_cumulative(true),
+                Some(&mask),
+            )
+            .unwrap();
+
+        // Values 6, 7, 8 are counted, 9 lies outside of the range
+        assert_eq!(histogram.counts, vec![0, 0, 0, 3]);
+        assert_eq!(histogram.total(), 3);
+    }
+
+    #[test]
+    fn test_statistics_are_cached_until_modified() {
+        let mut volume = test_volume();
+
+        let statistics = volume.statistics().unwrap();
+        assert_eq!((statistics.min, statistics.max), (0.0, 9.0));
+        assert_eq!(statistics.mean, 4.5);
+        assert!((statistics.std - 8.25f32.sqrt()).abs() < 1e-6);
+        assert_eq!((statistics.min_count, statistics.max_count), (10, 10));
+        // Half of the voxels lie in or below the bin of 4
+        assert!((statistics.percentile(50.0) - 4.0).abs() < 0.01);
+        assert!(std::ptr::eq(statistics, volume.statistics().unwrap()));
+
+        volume.data_mut().fill(3.0);
+
+        let statistics = volume.statistics().unwrap();
+        assert!(statistics.is_constant());
+        assert_eq!(statistics.percentile(90.0), 3.0);
+    }
+
+    #[test]
+    fn test_statistics_ignore_nan() {
+        let mut volume = test_volume();
+        volume.data_mut()[[0, 0, 0]] = f32::NAN;
+        let nan = Volume::new(Array3::from_elem((2, 2, 2), f32::NAN), (1.0, 1.0, 1.0));
+
+        let statistics = volume.statistics().unwrap();
+
+        assert_eq!(statistics.count, 99);
+        assert_eq!((statistics.min, statistics.min_count), (0.0, 9));
+        assert!((statistics.mean - 4.5 / 0.99).abs() < 1e-5);
+        assert_eq!(statistics.histogram.total(), 99);
+        assert!(nan.statistics().is_none());
+        assert!(Volume::default().statistics().is_none());
+    }
+}
@@ -1,4 +1,179 @@
+use crate::enums::Interpolation;
+
 use ndarray::ArrayView2;
+use ndarray::ArrayView3;
+use ndarray::ArrayViewMut1;
+use ndarray::Axis;
+use ndarray::CowArray;
+use ndarray::Dimension;
+use ndarray::Ix2;
+use ndarray::Zip;
+
+/// Maximum number of samples per axis a kernel covers
+const MAX_TAPS: usize = 6;
+
+/// Distance in voxels a sample position may lie outside of the volume
+pub(crate) const BOUNDS_TOLERANCE: f32 = 1e-3;
+
+/// Whether a voxel coordinate lies within an axis of `len` voxels, up to
+/// [`BOUNDS_TOLERANCE`]
+pub(crate) fn within_bounds(value: f32, len: usize) -> bool {
+    (-BOUNDS_TOLERANCE..=len as f32 - 1.0 + BOUNDS_TOLERANCE).contains(&value)
+}
+
+/// Pole of the cubic B-spline prefilter
+const BSPLINE_POLE: f64 = -0.267_949_192_431_122_7; // sqrt(3) - 2
+
+/// Separable interpolation kernels
+#[derive(Clone, Copy)]
+pub(crate) enum Kernel {
+    CatmullRom,
+    BSpline,
+    Lanczos,
+}
+
+impl Kernel {
+    pub(crate) fn from_interpolation(interpolation: &Interpolation) -> Option<Kernel> {
+        match interpolation {
+            Interpolation::Cubic(_) => Some(Kernel::CatmullRom),
+            Interpolation::BSpline(_) => Some(Kernel::BSpline),
+            Interpolation::Lanczos(_) => Some(Kernel::Lanczos),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    fn radius(&self) -> isize {
+        match self {
+            Kernel::CatmullRom | Kernel::BSpline => 2,
+            Kernel::Lanczos => 3,
+        }
+    }
+
+    #[inline]
+    fn weight(&self, t: f32) -> f32 {
+        let t = t.abs();
+        match self {
+            Kernel::CatmullRom => {
+                if t < 1.0 {
+                    (1.5 * t - 2.5) * t * t + 1.0
+                } else if t < 2.0 {
+                    ((-0.5 * t + 2.5) * t - 4.0) * t + 2.0
+                } else {
+                    0.0
+                }
+            }
+            Kernel::BSpline => {
+                if t < 1.0 {
+                    (0.5 * t - 1.0) * t * t + 2.0 / 3.0
+                } else if t < 2.0 {
+                    let u = 2.0 - t;
+                    u * u * u / 6.0
+                } else {
+                    0.0
+                }
+            }
+            Kernel::Lanczos => {
+                if t < f32::EPSILON {
+                    1.0
+                } else if t < 3.0 {
+                    let pi_t = std::f32::consts::PI * t;
+                    3.0 * pi_t.sin() * (pi_t / 3.0).sin() / (pi_t * pi_t)
+                } else {
+                    0.0
+                }
+            }
+        }
+    }
+
+    /// Get the mirrored sample indices and normalised weights around a position
+    #[inline]
+    fn taps(&self, position: f32, len: usize) -> ([usize; MAX_TAPS], [f32; MAX_TAPS], usize) {
+        let radius = self.radius();
+        let count = (2 * radius) as usize;
+        let start = position.floor() as isize - radius + 1;
+
+        let mut indices = [0; MAX_TAPS];
+        let mut weights = [0.0; MAX_TAPS];
+        let mut total = 0.0;
+        for tap in 0..count {
+            let index = start + tap as isize;
+            indices[tap] = mirror_index(index, len);
+            weights[tap] = self.weight(position - index as f32);
+            total += weights[tap];
+        }
+        for weight in weights.iter_mut().take(count) {
+            *weight /= total;
+        }
+
+        (indices, weights, count)
+    }
+}
+
+/// Reflect an index at the borders without repeating the edge sample
+#[inline]
+fn mirror_index(index: isize, len: usize) -> usize {
+    if len == 1 {
+        return 0;
+    }
+    let period = 2 * (len as isize - 1);
+    let index = index.rem_euclid(period);
+    if index >= len as isize {
+        (period - index) as usize
+    } else {
+        index as usize
+    }
+}
+
+/// Convert samples to cubic B-spline coefficients in place (Unser et al.
+/// recursive filtering with mirrored boundaries)
+fn bspline_prefilter_line(mut line: ArrayViewMut1<'_, f32>) {
+    let len = line.len();
+    if len < 2 {
+        return;
+    }
+
+    let z = BSPLINE_POLE;
+    let gain = (1.0 - z) * (1.0 - 1.0 / z);
+    let mut c: Vec<f64> = line.iter().map(|&v| f64::from(v) * gain).collect();
+
+    // Causal initialisation, truncated once the pole has decayed
+    let horizon = (1e-9_f64.ln() / z.abs().ln()).ceil() as usize;
+    c[0] = if horizon < len {
+        let mut zn = z;
+        let mut sum = c[0];
+        for value in c.iter().take(horizon).skip(1) {
+            sum += zn * value;
+            zn *= z;
+        }
+        sum
+    } else {
+        let iz = 1.0 / z;
+        let mut zn = z;
+        let mut z2n = z.powi(len as i32 - 1);
+        let mut sum = c[0] + z2n * c[len - 1];
+        z2n = z2n * z2n * iz;
+        for value in c.iter().take(len - 1).skip(1) {
+            sum += (zn + z2n) * value;
+            zn *= z;
+            z2n *= iz;
+        }
+        sum / (1.0 - zn * zn)
+    };
+    for k in 1..len {
+        c[k] += z * c[k - 1];
+    }
+
+    // Anti-causal initialisation and filter
+    c[len - 1] = (z / (z * z - 1.0)) * (z * c[len - 2] + c[len - 1]);
+    for k in (0..len - 1).rev() {
+        c[k] = z * (c[k + 1] - c[k]);
+    }
+
+    for (value, coefficient) in line.iter_mut().zip(c) {
+        *value = coefficient as f32;
+    }
+}
 
 pub(crate) struct Interpolator;
 
@@ -43,11 +218,186 @@ impl Interpolator {
 
         v0.mul_add(one_minus_dy, v1 * dy)
     }
+
+    #[inline]
+    pub(crate) fn trilinear_interpolate(volume: &ArrayView3<f32>, z: f32, y: f32, x: f32) -> f32 {
+        let (depth, height, width) = volume.dim();
+
+        let z0 = z.floor() as usize;
+        let y0 = y.floor() as usize;
+        let x0 = x.floor() as usize;
+        let z1 = (z0 + 1).min(depth - 1);
+        let y1 = (y0 + 1).min(height - 1);
+        let x1 = (x0 + 1).min(width - 1);
+
+        let dz = z - z0 as f32;
+        let dy = y - y0 as f32;
+        let dx = x - x0 as f32;
+        let one_minus_dx = 1.0 - dx;
+        let one_minus_dy = 1.0 - dy;
+        let one_minus_dz = 1.0 - dz;
+
+        let v000 = volume[[z0, y0, x0]];
+        let v001 = volume[[z0, y0, x1]];
+        let v010 = volume[[z0, y1, x0]];
+        let v011 = volume[[z0, y1, x1]];
+        let v100 = volume[[z1, y0, x0]];
+        let v101 = volume[[z1, y0, x1]];
+        let v110 = volume[[z1, y1, x0]];
+        let v111 = volume[[z1, y1, x1]];
+
+        let v00 = v000.mul_add(one_minus_dx, v001 * dx);
+        let v01 = v010.mul_add(one_minus_dx, v011 * dx);
+        let v10 = v100.mul_add(one_minus_dx, v101 * dx);
+        let v11 = v110.mul_add(one_minus_dx, v111 * dx);
+
+        let v0 = v00.mul_add(one_minus_dy, v01 * dy);
+        let v1 = v10.mul_add(one_minus_dy, v11 * dy);
+
+        v0.mul_add(one_minus_dz, v1 * dz)
+    }
+
+    /// Replace samples by cubic B-spline coefficients along every axis
+    pub(crate) fn bspline_prefilter<D: Dimension>(data: &mut ndarray::Array<f32, D>) {
+        for axis in 0..data.ndim() {
+            Zip::from(data.lanes_mut(Axis(axis))).par_for_each(bspline_prefilter_line);
+        }
+    }
+
+    /// Get the data to sample a slice from, i.e. B-spline coefficients for
+    /// `BSpline` and the slice itself otherwise
+    pub(crate) fn prepare_slice<'a>(
+        slice: ArrayView2<'a, f32>,
+        interpolation: &Interpolation,
+    ) -> CowArray<'a, f32, Ix2> {
+        match interpolation {
+            Interpolation::BSpline(_) => {
+                let mut coefficients = slice.to_owned();
+                Self::bspline_prefilter(&mut coefficients);
+                CowArray::from(coefficients)
+            }
+            _ => CowArray::from(slice),
+        }
+    }
+
+    #[inline]
+    pub(crate) fn separable_interpolate_2d(
+        slice: &ArrayView2<f32>,
+        y: f32,
+        x: f32,
+        kernel: Kernel,
+    ) -> f32 {
+        let (height, width) = slice.dim();
+        let (rows, row_weights, count) = kernel.taps(y, height);
+        let (cols, col_weights, _) = kernel.taps(x, width);
+
+        let mut sum = 0.0;
+        for i in 0..count {
+            let mut row_sum = 0.0;
+            for j in 0..count {
+                row_sum += slice[[rows[i], cols[j]]] * col_weights[j];
+            }
+            sum += row_sum * row_weights[i];
+        }
+        sum
+    }
+
+    #[inline]
+    pub(crate) fn separable_interpolate_3d(
+        volume: &ArrayView3<f32>,
+        z: f32,
+        y: f32,
+        x: f32,
+        kernel: Kernel,
+    ) -> f32 {
+        let (depth, height, width) = volume.dim();
+        let (slices, slice_weights, count) = kernel.taps(z, depth);
+        let (rows, row_weights, _) = kernel.taps(y, height);
+        let (cols, col_weights, _) = kernel.taps(x, width);
+
+        let mut sum = 0.0;
+        for k in 0..count {
+            let mut slice_sum = 0.0;
+            for i in 0..count {
+                let mut row_sum = 0.0;
+                for j in 0..count {
+                    row_sum += volume[[slices[k], rows[i], cols[j]]] * col_weights[j];
+                }
+                slice_sum += row_sum * row_weights[i];
+            }
+            sum += slice_sum * slice_weights[k];
+        }
+        sum
+    }
+
+    /// Sample a slice at continuous coordinates within its bounds. For
+    /// `BSpline` the slice must hold the coefficients from [`Interpolator::prepare_slice`].
+    #[inline]
+    pub(crate) fn sample_slice(
+        slice: &ArrayView2<f32>,
+        y: f32,
+        x: f32,
+        interpolation: &Interpolation,
+    ) -> f32 {
+        match Kernel::from_interpolation(interpolation) {
+            Some(kernel) => Self::separable_interpolate_2d(slice, y, x, kernel),
+            None => match interpolation {
+                Interpolation::None | Interpolation::NearestNeighbor(_) => {
+                    slice[[y.round() as usize, x.round() as usize]]
+                }
+                _ => Self::bilinear_interpolate(slice, y, x),
+            },
+        }
+    }
+
+    /// Sample the volume at continuous voxel coordinates. Returns `None` if
+    /// the position lies outside of the volume.
+    ///
+    /// `Bilinear` has no plane to interpolate within and samples trilinearly,
+    /// planes are snapped beforehand through [`plane_sampling`]. For `BSpline`
+    /// the volume must hold the coefficients from [`Interpolator::bspline_prefilter`].
+    ///
+    /// [`plane_sampling`]: crate::backend::plane_sampling
+    pub(crate) fn sample_volume(
+        volume: &ArrayView3<f32>,
+        z: f32,
+        y: f32,
+        x: f32,
+        interpolation: &Interpolation,
+    ) -> Option<f32> {
+        if volume.is_empty() {
+            return None;
+        }
+        let (depth, height, width) = volume.dim();
+        // Accept positions a rounding error outside of the volume
+        let clamp = |value: f32, len: usize| {
+            within_bounds(value, len).then(|| value.clamp(0.0, len as f32 - 1.0))
+        };
+        let z = clamp(z, depth)?;
+        let y = clamp(y, height)?;
+        let x = clamp(x, width)?;
+
+        match interpolation {
+            Interpolation::None | Interpolation::NearestNeighbor(_) => {
+                Some(volume[[z.round() as usize, y.round() as usize, x.round() as usize]])
+            }
+            Interpolation::Bilinear(_) | Interpolation::Trilinear(_) => {
+                Some(Self::trilinear_interpolate(volume, z, y, x))
+            }
+            Interpolation::Cubic(_) | Interpolation::BSpline(_) | Interpolation::Lanczos(_) => {
+                let kernel = Kernel::from_interpolation(interpolation)?;
+                Some(Self::separable_interpolate_3d(volume, z, y, x, kernel))
+            }
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::enums::Processor;
     use ndarray::Array2;
+    use ndarray::Array3;
+    use ndarray::Axis;
 
     use super::*;
 
@@ -214,4 +564,249 @@ mod tests {
         // result = 2.25 * 0.25 + 5.25 * 0.75 = 4.5
         assert!((result - 4.5).abs() < 1e-6);
     }
+
+    #[test]
+    fn test_sample_volume_out_of_bounds() {
+        let data = Array3::<f32>::zeros((2, 2, 2));
+        let view = data.view();
+
+        let result = Interpolator::sample_volume(&view, 0.0, 2.5, 0.0, &Interpolation::None);
+
+        assert_eq!(result, None);
+    }
+
+    #[test]
+    fn test_sample_volume_bilinear_between_slices_is_trilinear() {
+        let data =
+            Array3::from_shape_vec((2, 2, 2), vec![0.0, 2.0, 4.0, 6.0, 10.0, 10.0, 10.0, 10.0])
+                .unwrap();
+        let view = data.view();
+
+        let result = Interpolator::sample_volume(
+            &view,
+            0.2,
+            0.5,
+            0.5,
+            &Interpolation::Bilinear(Processor::CPU),
+        );
+
+        // 3.0 in the first slice, 10.0 in the second one
+        assert_eq!(result, Some(3.0 * 0.8 + 10.0 * 0.2));
+    }
+
+    #[test]
+    fn test_trilinear_interpolate_at_exact_point() {
+        let data = Array3::from_shape_fn((3, 3, 3), |(z, y, x)| (z * 9 + y * 3 + x) as f32);
+        let view = data.view();
+
+        let result = Interpolator::trilinear_interpolate(&view, 1.0, 2.0, 0.0);
+
+        assert_eq!(result, 15.0);
+    }
+
+    #[test]
+    fn test_trilinear_interpolate_center() {
+        let data = Array3::from_shape_vec((2, 2, 2), vec![0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0])
+            .unwrap();
+        let view = data.view();
+
+        let result = Interpolator::trilinear_interpolate(&view, 0.5, 0.5, 0.5);
+
+        // Average of all eight corners
+        assert_eq!(result, 3.5);
+    }
+
+    #[test]
+    fn test_trilinear_interpolate_depth_midpoint() {
+        let data = Array3::from_shape_vec((2, 1, 1), vec![2.0, 6.0]).unwrap();
+        let view = data.view();
+
+        let result = Interpolator::trilinear_interpolate(&view, 0.25, 0.0, 0.0);
+
+        assert_eq!(result, 3.0);
+    }
+
+    #[test]
+    fn test_trilinear_interpolate_linear_function() {
+        // Trilinear interpolation reproduces linear functions exactly
+        let data = Array3::from_shape_fn((4, 4, 4), |(z, y, x)| {
+            2.0 * z as f32 - y as f32 + 0.5 * x as f32
+        });
+        let view = data.view();
+
+        let result = Interpolator::trilinear_interpolate(&view, 1.3, 2.6, 0.7);
+
+        assert!((result - (2.0 * 1.3 - 2.6 + 0.5 * 0.7)).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_trilinear_interpolate_boundary_clamping() {
+        let data = Array3::from_shape_fn((2, 2, 2), |(z, y, x)| (z * 4 + y * 2 + x) as f32);
+        let view = data.view();
+
+        let result = Interpolator::trilinear_interpolate(&view, 1.0, 1.0, 1.0);
+
+        assert_eq!(result, 7.0);
+    }
+
+    fn sine_slice(size: usize) -> Array2<f32> {
+        Array2::from_shape_fn((size, size), |(y, x)| {
+            (0.4 * y as f32).sin() * (0.3 * x as f32).cos()
+        })
+    }
+
+    fn max_error_2d(slice: &Array2<f32>, interpolation: &Interpolation) -> f32 {
+        let coefficients = Interpolator::prepare_slice(slice.view(), interpolation);
+        let view = coefficients.view();
+        let mut max_error: f32 = 0.0;
+        // Stay away from the borders where the mirrored boundary dominates
+        for i in 0..40 {
+            let y = 8.0 + i as f32 * 0.37;
+            let x = 8.0 + i as f32 * 0.29;
+            let expected = (0.4 * y).sin() * (0.3 * x).cos();
+            let result = Interpolator::sample_slice(&view, y, x, interpolation);
+            max_error = max_error.max((result - expected).abs());
+        }
+        max_error
+    }
+
+    #[test]
+    fn test_kernel_weights_at_integers() {
+        for kernel in [Kernel::CatmullRom, Kernel::Lanczos] {
+            assert_eq!(kernel.weight(0.0), 1.0);
+            assert!(kernel.weight(1.0).abs() < 1e-6);
+            assert!(kernel.weight(2.0).abs() < 1e-6);
+        }
+        assert!((Kernel::BSpline.weight(0.0) - 2.0 / 3.0).abs() < 1e-6);
+        assert!((Kernel::BSpline.weight(1.0) - 1.0 / 6.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_mirror_index() {
+        assert_eq!(mirror_index(-1, 4), 1);
+        assert_eq!(mirror_index(-2, 4), 2);
+        assert_eq!(mirror_index(4, 4), 2);
+        assert_eq!(mirror_index(5, 4), 1);
+        assert_eq!(mirror_index(3, 1), 0);
+    }
+
+    #[test]
+    fn test_cubic_reproduces_quadratic() {
+        let data = Array2::from_shape_fn((8, 8), |(y, x)| {
+            let (y, x) = (y as f32, x as f32);
+            y * y - 2.0 * x * y + 3.0 * x
+        });
+        let view = data.view();
+
+        let result = Interpolator::separable_interpolate_2d(&view, 3.4, 4.7, Kernel::CatmullRom);
+
+        let expected = 3.4 * 3.4 - 2.0 * 4.7 * 3.4 + 3.0 * 4.7;
+        assert!((result - expected).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_bspline_reproduces_cubic() {
+        let data = Array2::from_shape_fn((24, 24), |(y, x)| {
+            let (y, x) = (y as f32 / 8.0, x as f32 / 8.0);
+            y * y * y - x * x * y + 0.5 * x
+        });
+        let interpolation = Interpolation::BSpline(Processor::CPU);
+        let coefficients = Interpolator::prepare_slice(data.view(), &interpolation);
+
+        let result = Interpolator::sample_slice(&coefficients.view(), 11.3, 12.6, &interpolation);
+
+        let (y, x) = (11.3 / 8.0, 12.6 / 8.0);
+        let expected = y * y * y - x * x * y + 0.5 * x;
+        assert!((result - expected).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_bspline_passes_through_samples() {
+        let data = sine_slice(12);
+        let interpolation = Interpolation::BSpline(Processor::CPU);
+        let coefficients = Interpolator::prepare_slice(data.view(), &interpolation);
+        let view = coefficients.view();
+
+        for ((y, x), &expected) in data.indexed_iter() {
+            let result = Interpolator::sample_slice(&view, y as f32, x as f32, &interpolation);
+            assert!((result - expected).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn test_lanczos_passes_through_samples() {
+        let data = sine_slice(12);
+        let view = data.view();
+
+        for ((y, x), &expected) in data.indexed_iter() {
+            let result =
+                Interpolator::separable_interpolate_2d(&view, y as f32, x as f32, Kernel::Lanczos);
+            assert!((result - expected).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_higher_order_kernels_beat_bilinear_on_smooth_function() {
+        let data = sine_slice(32);
+
+        let bilinear = max_error_2d(&data, &Interpolation::Bilinear(Processor::CPU));
+        let cubic = max_error_2d(&data, &Interpolation::Cubic(Processor::CPU));
+        let bspline = max_error_2d(&data, &Interpolation::BSpline(Processor::CPU));
+        let lanczos = max_error_2d(&data, &Interpolation::Lanczos(Processor::CPU));
+
+        assert!(cubic < bilinear);
+        assert!(bspline < bilinear);
+        assert!(lanczos < bilinear);
+        assert!(cubic < 2e-3);
+        assert!(bspline < 5e-4);
+        assert!(lanczos < 1e-2);
+    }
+
+    #[test]
+    fn test_cubic_3d_on_smooth_function() {
+        let data = Array3::from_shape_fn((16, 16, 16), |(z, y, x)| {
+            (0.3 * z as f32).sin() + (0.2 * y as f32).cos() * (0.25 * x as f32).sin()
+        });
+        let (z, y, x): (f32, f32, f32) = (7.3, 8.6, 6.2);
+        let expected = (0.3 * z).sin() + (0.2 * y).cos() * (0.25 * x).sin();
+
+        for interpolation in [
+            Interpolation::Cubic(Processor::CPU),
+            Interpolation::BSpline(Processor::CPU),
+            Interpolation::Lanczos(Processor::CPU),
+        ] {
+            let mut coefficients = data.clone();
+            if matches!(interpolation, Interpolation::BSpline(_)) {
+                Interpolator::bspline_prefilter(&mut coefficients);
+            }
+            let trilinear = Interpolator::trilinear_interpolate(&data.view(), z, y, x);
+
+            let result =
+                Interpolator::sample_volume(&coefficients.view(), z, y, x, &interpolation).unwrap();
+
+            assert!((result - expected).abs() < (trilinear - expected).abs());
+            assert!((result - expected).abs() < 2e-3);
+        }
+    }
+
+    #[test]
+    fn test_nearest_neighbor_keeps_labels() {
+        let data = Array3::from_shape_fn((4, 4, 4), |(z, y, x)| ((z + y + x) % 3) as f32);
+        let view = data.view();
+        let interpolation = Interpolation::NearestNeighbor(Processor::CPU);
+
+        let result = Interpolator::sample_volume(&view, 1.4, 2.6, 0.5, &interpolation);
+
+        assert_eq!(result, Some(data[[1, 3, 1]]));
+        for i in 0..20 {
+            let position = i as f32 * 0.15;
+            let value = Interpolator::sample_slice(
+                &data.index_axis(Axis(0), 2),
+                position,
+                3.0 - position,
+                &interpolation,
+            );
+            assert_eq!(value.fract(), 0.0);
+        }
+    }
 }
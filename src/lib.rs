@@ -16,7 +16,11 @@
 //!
 //!  Library consumers can chose whether the Coronal and Sagittal slices
 //!  should be interpolated to preserve the aspect ratios between of the
-//!  images. DICOM files are assumed to have the following attributes:
+//!  images. Slices can also be sampled at fractional positions using
+//!  trilinear, cubic (Catmull-Rom, B-spline) or Lanczos interpolation. Curved planar reformations can be sampled along a centreline
+//!  given in voxel or patient coordinates. Thick-slab and full volume
+//!  projections (MIP, MinIP, mean and sum) are available in every axis.
+//!  Volumes can be resampled to an isotropic grid, a given spacing or shape. DICOM files are assumed to have the following attributes:
 //!   - Axial data set (Only Coronal and Sagittal axes are interpolated)
 //!   - No multiframe (always the first frame is used)
 //!   - Images from the same series (Series Instance UID) and acquisition
@@ -24,11 +28,14 @@
 //!
 //!   Contributions are highly welcome!
 //!
+//! Computations run on a [`Processor`] backend: a multi-threaded rayon
+//! backend, a vectorised AVX2 variant of it, a single-threaded reference
+//! backend or a custom implementation of [`ComputeBackend`]. With the `gpu`
+//! feature, `Processor::GPU` runs resampling and projections as wgpu compute
+//! shaders.
+//!
 //! # Roadmap
 //!
-//!  - GPU processor for interpolation using WGPU and compute shaders
-//!  - Trilinear interpolation
-//!  - Cubic interpolation
 //!  - Caching of images
 //!
 //! # Examples
@@ -56,7 +63,57 @@
 //!
 //! [`FileDicomObject<InMemDicomObject>`]: https://docs.rs/dicom-object/latest/dicom_object/struct.FileDicomObject.html
 
+pub mod backend;
+pub mod colormap;
+pub mod crosshair;
+pub mod distance;
 pub mod enums;
+pub mod filter;
+pub mod fusion;
+pub mod geometry;
+#[cfg(feature = "gpu")]
+pub mod gpu;
+pub mod gradient;
+pub mod histogram;
 mod interpolator;
+pub mod mapping;
+pub mod measurement;
+pub mod morphology;
+pub mod output;
+pub mod overlay;
+pub mod projection;
+pub mod reformat;
+pub mod region_growing;
+pub mod render;
+pub mod resample;
+pub mod segmentation;
+pub mod simd;
+pub mod statistics;
 pub mod volume;
 pub mod volume_loader;
+
+pub use backend::{ComputeBackend, CpuBackend, PlaneGrid, ScalarBackend, register_backend};
+pub use colormap::{ColorMapping, Colormap, PaletteColorLut};
+pub use crosshair::{ReferenceLine, SliceIndices};
+pub use enums::{
+    Connectivity, Filter, GradientOperator, GrowingCriterion, Interpolation, Morphology,
+    Orientation, OverlayStyle, Processor, ProjectionMode, RenderMode, ResampleTarget, SortBy,
+    StructuringElement,
+};
+pub use geometry::Point3;
+#[cfg(feature = "gpu")]
+pub use gpu::{GpuBackend, GpuError};
+pub use gradient::Gradient;
+pub use histogram::{Histogram, HistogramOptions, VolumeStatistics};
+pub use mapping::PixelMapping;
+pub use measurement::{Measurement, MeasurementValue};
+pub use output::LinearMapping;
+pub use overlay::LabelOverlay;
+pub use reformat::{CoordinateSpace, CurveType, CurvedPlanarReformat, ReformatMode};
+pub use region_growing::{RegionGrowing, RegionGrowingError};
+pub use render::{Camera, CameraProjection, TransferFunction, VolumeRendering};
+pub use segmentation::{Component, Components};
+pub use simd::SimdBackend;
+pub use statistics::{Region, Roi, RoiStatistics};
+pub use volume::Volume;
+pub use volume_loader::{VolumeLoader, VolumeLoaderError};
@@ -0,0 +1,272 @@
+use crate::backend::PlaneGrid;
+use crate::enums::Interpolation;
+use crate::enums::Orientation;
+use crate::geometry::Point3;
+use crate::reformat::CurvedPlanarReformat;
+use crate::volume::Volume;
+
+use ndarray::Array2;
+use ndarray::Axis;
+
+/// Maximum distance in voxels between a position and the closest sample of
+/// a curved reformation to be considered on the image
+const CURVE_TOLERANCE: f32 = 1.0;
+
+/// Mapping between output image pixels and continuous voxel coordinates
+///
+/// Pixel coordinates are (x = column, y = row) with integer values at the
+/// pixel centres. Pixels within half a pixel of the image border are
+/// considered inside the image.
+#[derive(Clone, Debug)]
+pub enum PixelMapping {
+    /// Slices, slabs and projections: a plane grid with orthogonal steps
+    Plane(PlaneGrid),
+    /// Curved reformations: the voxel position of every pixel
+    Curved(Array2<Point3>),
+}
+
+impl PixelMapping {
+    /// Get the (height, width) of the mapped image
+    pub fn dim(&self) -> (usize, usize) {
+        match self {
+            PixelMapping::Plane(grid) => grid.dim,
+            PixelMapping::Curved(positions) => positions.dim(),
+        }
+    }
+
+    fn contains(&self, x: f32, y: f32) -> bool {
+        let (height, width) = self.dim();
+        (-0.5..=width as f32 - 0.5).contains(&x) && (-0.5..=height as f32 - 0.5).contains(&y)
+    }
+
+    /// Convert pixel coordinates to continuous voxel coordinates
+    ///
+    /// Returns `None` outside of the image. Curved reformations are linearly
+    /// interpolated between the positions of the neighbouring pixels.
+    pub fn pixel_to_voxel(&self, x: f32, y: f32) -> Option<Point3> {
+        if !self.contains(x, y) {
+            return None;
+        }
+
+        match self {
+            PixelMapping::Plane(grid) => Some(grid.origin + grid.row_step * y + grid.col_step * x),
+            PixelMapping::Curved(positions) => {
+                let (height, width) = positions.dim();
+                let y = y.clamp(0.0, (height - 1) as f32);
+                let x = x.clamp(0.0, (width - 1) as f32);
+                let (y0, x0) = (y.floor() as usize, x.floor() as usize);
+                let (y1, x1) = ((y0 + 1).min(height - 1), (x0 + 1).min(width - 1));
+                let (dy, dx) = (y - y0 as f32, x - x0 as f32);
+                let top = positions[[y0, x0]] * (1.0 - dx) + positions[[y0, x1]] * dx;
+                let bottom = positions[[y1, x0]] * (1.0 - dx) + positions[[y1, x1]] * dx;
+                Some(top * (1.0 - dy) + bottom * dy)
+            }
+        }
+    }
+
+    /// Convert continuous voxel coordinates to pixel coordinates
+    ///
+    /// Positions off a plane are projected onto it, e.g. to locate a voxel
+    /// in a projection. For curved reformations the closest pixel is
+    /// returned if its sample lies within one voxel. Returns `None` if the
+    /// position does not map onto the image.
+    pub fn voxel_to_pixel(&self, voxel: Point3) -> Option<(f32, f32)> {
+        let (x, y) = match self {
+            PixelMapping::Plane(grid) => {
+                let offset = voxel - grid.origin;
+                let along = |step: Point3| {
+                    let length = step.dot(&step);
+                    if length > 0.0 {
+                        offset.dot(&step) / length
+                    } else {
+                        0.0
+                    }
+                };
+                (along(grid.col_step), along(grid.row_step))
+            }
+            PixelMapping::Curved(positions) => {
+                let ((row, col), distance) = positions
+                    .indexed_iter()
+                    .map(|(index, position)| (index, position.distance(&voxel)))
+                    .min_by(|a, b| a.1.total_cmp(&b.1))?;
+                if distance > CURVE_TOLERANCE {
+                    return None;
+                }
+                (col as f32, row as f32)
+            }
+        };
+        self.contains(x, y).then_some((x, y))
+    }
+}
+
+impl Volume {
+    /// Convert pixel coordinates of a mapped image to patient coordinates in mm
+    pub fn pixel_to_patient(&self, mapping: &PixelMapping, x: f32, y: f32) -> Option<Point3> {
+        mapping
+            .pixel_to_voxel(x, y)
+            .map(|voxel| self.voxel_to_patient(voxel))
+    }
+
+    /// Convert patient coordinates in mm to pixel coordinates of a mapped image
+    pub fn patient_to_pixel(&self, mapping: &PixelMapping, patient: Point3) -> Option<(f32, f32)> {
+        mapping.voxel_to_pixel(self.patient_to_voxel(patient))
+    }
+
+    /// Get the pixel mapping of [`Volume::get_image_from_axis`] and the other
+    /// slice APIs taking an index
+    pub fn get_pixel_mapping_from_axis(
+        &self,
+        index: usize,
+        orientation: Orientation,
+        interpolation: &Interpolation,
+    ) -> Option<PixelMapping> {
+        self.get_pixel_mapping_at_position(index as f32, orientation, interpolation)
+    }
+
+    /// Get the pixel mapping of [`Volume::get_slice_at_position`] and the
+    /// other slice APIs taking a fractional index
+    pub fn get_pixel_mapping_at_position(
+        &self,
+        position: f32,
+        orientation: Orientation,
+        interpolation: &Interpolation,
+    ) -> Option<PixelMapping> {
+        self.get_plane_grid(position, orientation, interpolation)
+            .map(PixelMapping::Plane)
+    }
+
+    /// Get the pixel mapping of [`Volume::get_projection_image_from_axis`]
+    ///
+    /// Pixels map onto the centre of the slab.
+    pub fn get_projection_pixel_mapping_from_axis(
+        &self,
+        index: usize,
+        thickness: f32,
+        orientation: Orientation,
+        interpolation: &Interpolation,
+    ) -> Option<PixelMapping> {
+        let (start, end) = self.get_slab_range(index, thickness, &orientation)?;
+        let center = (start + end - 1) as f32 * 0.5;
+        self.get_pixel_mapping_at_position(center, orientation, interpolation)
+    }
+
+    /// Get the pixel mapping of [`Volume::get_full_projection_image`]
+    ///
+    /// Pixels map onto the centre of the volume.
+    pub fn get_full_projection_pixel_mapping(
+        &self,
+        orientation: Orientation,
+        interpolation: &Interpolation,
+    ) -> Option<PixelMapping> {
+        let len = self.data.len_of(Axis(orientation.axis()));
+        let center = len.saturating_sub(1) as f32 * 0.5;
+        self.get_pixel_mapping_at_position(center, orientation, interpolation)
+    }
+
+    /// Get the pixel mapping of [`Volume::get_curved_reformat`]
+    pub fn get_curved_reformat_pixel_mapping(
+        &self,
+        cpr: &CurvedPlanarReformat,
+    ) -> Option<PixelMapping> {
+        self.get_curved_reformat_positions(cpr)
+            .map(PixelMapping::Curved)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::enums::Processor;
+    use crate::geometry::IDENTITY_DIRECTION;
+    use crate::reformat::CoordinateSpace;
+    use ndarray::Array3;
+
+    /// 3 slices at 2 mm of 4 rows and 5 columns at 1 mm
+    fn test_volume() -> Volume {
+        let data = Array3::from_shape_fn((3, 4, 5), |(z, y, x)| (z * 100 + y * 10 + x) as f32);
+        Volume::new(data, (1.0, 1.0, 2.0))
+            .with_geometry(Point3::new(-10.0, 20.0, 5.0), IDENTITY_DIRECTION)
+    }
+
+    #[test]
+    fn test_stretched_coronal_pixel_round_trip() {
+        let volume = test_volume();
+        let interpolation = Interpolation::Trilinear(Processor::CPU);
+
+        let mapping = volume
+            .get_pixel_mapping_from_axis(2, Orientation::Coronal, &interpolation)
+            .unwrap();
+        let slice = volume
+            .get_resampled_slice_from_axis(2, Orientation::Coronal, interpolation)
+            .unwrap();
+
+        // Depth is stretched from 3 slices at 2 mm to 6 rows
+        assert_eq!(mapping.dim(), slice.dim());
+        let voxel = mapping.pixel_to_voxel(3.0, 5.0).unwrap();
+        assert_eq!(voxel, Point3::new(3.0, 2.0, 2.0));
+        assert_eq!(slice[[5, 3]], 223.0);
+        let (x, y) = mapping.voxel_to_pixel(Point3::new(1.0, 2.0, 0.8)).unwrap();
+        assert!((x - 1.0).abs() < 1e-5 && (y - 2.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_patient_mapping() {
+        let volume = test_volume();
+
+        let mapping = volume
+            .get_pixel_mapping_from_axis(1, Orientation::Axial, &Interpolation::None)
+            .unwrap();
+
+        let patient = volume.pixel_to_patient(&mapping, 4.0, 2.0).unwrap();
+        assert_eq!(patient, Point3::new(-6.0, 22.0, 7.0));
+        assert_eq!(volume.patient_to_pixel(&mapping, patient), Some((4.0, 2.0)));
+        assert_eq!(
+            volume.patient_to_pixel(&mapping, Point3::new(0.0, 22.0, 7.0)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_projection_maps_to_slab_centre() {
+        let volume = test_volume();
+
+        let mapping = volume
+            .get_projection_pixel_mapping_from_axis(
+                1,
+                2.0,
+                Orientation::Sagittal,
+                &Interpolation::None,
+            )
+            .unwrap();
+
+        assert_eq!(mapping.dim(), (3, 4));
+        assert_eq!(
+            mapping.pixel_to_voxel(1.0, 2.0),
+            Some(Point3::new(1.5, 1.0, 2.0))
+        );
+    }
+
+    #[test]
+    fn test_curved_reformat_mapping() {
+        let volume = Volume::new(Array3::zeros((5, 5, 5)), (1.0, 1.0, 1.0));
+        let cpr = CurvedPlanarReformat::new(
+            vec![Point3::new(0.0, 2.0, 2.0), Point3::new(4.0, 2.0, 2.0)],
+            CoordinateSpace::Voxel,
+        )
+        .with_width(2.0);
+
+        let mapping = volume.get_curved_reformat_pixel_mapping(&cpr).unwrap();
+
+        assert_eq!(mapping.dim(), (3, 5));
+        // Row 0 lies on the side of the up vector
+        assert_eq!(
+            mapping.pixel_to_voxel(2.5, 0.0),
+            Some(Point3::new(2.5, 2.0, 3.0))
+        );
+        assert_eq!(
+            mapping.voxel_to_pixel(Point3::new(3.0, 2.0, 1.1)),
+            Some((3.0, 2.0))
+        );
+        assert_eq!(mapping.voxel_to_pixel(Point3::new(3.0, 4.0, 2.0)), None);
+    }
+}
@@ -0,0 +1,259 @@
+use crate::geometry::Point3;
+use crate::volume::Volume;
+
+use serde::{Deserialize, Serialize};
+use std::f32::consts::PI;
+
+/// A measurement drawn on a volume
+///
+/// All points are continuous voxel coordinates (x = column, y = row,
+/// z = slice), e.g. obtained from clicked pixels through
+/// [`PixelMapping::pixel_to_voxel`](crate::mapping::PixelMapping::pixel_to_voxel).
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Measurement {
+    Distance {
+        start: Point3,
+        end: Point3,
+    },
+    Polyline {
+        points: Vec<Point3>,
+    },
+    /// Angle at `vertex` between the rays to `first` and `second`
+    Angle {
+        vertex: Point3,
+        first: Point3,
+        second: Point3,
+    },
+    /// Closed polygon, the last point connects to the first
+    Polygon {
+        points: Vec<Point3>,
+    },
+    /// Ellipse given by its centre and the end points of its two semi-axes
+    ///
+    /// Only the part of the second semi-axis perpendicular to the first one
+    /// in mm is used.
+    Ellipse {
+        center: Point3,
+        first_axis: Point3,
+        second_axis: Point3,
+    },
+}
+
+/// Result of a measurement in mm, mm² or degrees
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MeasurementValue {
+    Length { length: f32 },
+    Angle { degrees: f32 },
+    Area { area: f32, perimeter: f32 },
+}
+
+/// Get the length of a polyline
+fn path_length(points: &[Point3]) -> f32 {
+    points
+        .windows(2)
+        .map(|pair| pair[0].distance(&pair[1]))
+        .sum()
+}
+
+impl Volume {
+    /// Get the distance in mm between two points in voxel coordinates
+    pub fn distance_mm(&self, start: Point3, end: Point3) -> f32 {
+        self.voxel_to_patient(start)
+            .distance(&self.voxel_to_patient(end))
+    }
+
+    /// Evaluate a measurement in mm using the spacing and geometry of the volume
+    ///
+    /// Returns `None` for degenerate measurements, e.g. a polygon with fewer
+    /// than three points or an angle with a zero length ray.
+    pub fn measure(&self, measurement: &Measurement) -> Option<MeasurementValue> {
+        let mm = |points: &[Point3]| -> Vec<Point3> {
+            points
+                .iter()
+                .map(|&point| self.voxel_to_patient(point))
+                .collect()
+        };
+
+        match measurement {
+            Measurement::Distance { start, end } => Some(MeasurementValue::Length {
+                length: self.distance_mm(*start, *end),
+            }),
+            Measurement::Polyline { points } => {
+                if points.len() < 2 {
+                    return None;
+                }
+                Some(MeasurementValue::Length {
+                    length: path_length(&mm(points)),
+                })
+            }
+            Measurement::Angle {
+                vertex,
+                first,
+                second,
+            } => {
+                let vertex = self.voxel_to_patient(*vertex);
+                let first = (self.voxel_to_patient(*first) - vertex).normalize()?;
+                let second = (self.voxel_to_patient(*second) - vertex).normalize()?;
+                let cos = first.dot(&second).clamp(-1.0, 1.0);
+                Some(MeasurementValue::Angle {
+                    degrees: cos.acos().to_degrees(),
+                })
+            }
+            Measurement::Polygon { points } => {
+                if points.len() < 3 {
+                    return None;
+                }
+                let mut points = mm(points);
+                points.push(points[0]);
+                // Vector area, exact for planar polygons of any orientation
+                let origin = points[0];
+                let normal = points.windows(2).fold(Point3::default(), |sum, pair| {
+                    sum + (pair[0] - origin).cross(&(pair[1] - origin))
+                });
+                Some(MeasurementValue::Area {
+                    area: normal.length() * 0.5,
+                    perimeter: path_length(&points),
+                })
+            }
+            Measurement::Ellipse {
+                center,
+                first_axis,
+                second_axis,
+            } => {
+                let center = self.voxel_to_patient(*center);
+                let first = self.voxel_to_patient(*first_axis) - center;
+                let second = self.voxel_to_patient(*second_axis) - center;
+                let a = first.length();
+                let direction = first.normalize()?;
+                let b = (second - direction * second.dot(&direction)).length();
+                // Ramanujan's second approximation of the perimeter
+                let h = ((a - b) / (a + b)).powi(2);
+                let perimeter = PI * (a + b) * (1.0 + 3.0 * h / (10.0 + (4.0 - 3.0 * h).sqrt()));
+                Some(MeasurementValue::Area {
+                    area: PI * a * b,
+                    perimeter,
+                })
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::Array3;
+
+    /// Anisotropic volume with 0.5 × 1 × 2 mm voxels
+    fn test_volume() -> Volume {
+        Volume::new(Array3::zeros((4, 4, 4)), (0.5, 1.0, 2.0))
+    }
+
+    #[test]
+    fn test_distance_and_polyline_respect_spacing() {
+        let volume = test_volume();
+        let points = vec![
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(6.0, 0.0, 0.0),
+            Point3::new(6.0, 4.0, 0.0),
+            Point3::new(6.0, 4.0, 1.5),
+        ];
+
+        let distance = volume.measure(&Measurement::Distance {
+            start: points[0],
+            end: points[2],
+        });
+        let polyline = volume.measure(&Measurement::Polyline { points });
+
+        assert_eq!(distance, Some(MeasurementValue::Length { length: 5.0 }));
+        assert_eq!(polyline, Some(MeasurementValue::Length { length: 10.0 }));
+    }
+
+    #[test]
+    fn test_angle_in_mm() {
+        let volume = test_volume();
+
+        // 45° in voxel coordinates, but the voxels are four times as deep as wide
+        let Some(MeasurementValue::Angle { degrees }) = volume.measure(&Measurement::Angle {
+            vertex: Point3::new(0.0, 0.0, 0.0),
+            first: Point3::new(2.0, 0.0, 0.0),
+            second: Point3::new(2.0, 0.0, 1.0),
+        }) else {
+            panic!("should have measured an angle");
+        };
+
+        // Rays of 1 mm along x and (1, 0, 2) mm
+        assert!((degrees - 2.0f32.atan().to_degrees()).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_polygon_and_ellipse_on_coronal_plane() {
+        let volume = test_volume();
+        let polygon = Measurement::Polygon {
+            points: vec![
+                Point3::new(0.0, 1.0, 0.0),
+                Point3::new(4.0, 1.0, 0.0),
+                Point3::new(4.0, 1.0, 2.0),
+                Point3::new(0.0, 1.0, 2.0),
+            ],
+        };
+        let circle = Measurement::Ellipse {
+            center: Point3::new(2.0, 1.0, 1.0),
+            first_axis: Point3::new(6.0, 1.0, 1.0),
+            second_axis: Point3::new(2.0, 1.0, 2.0),
+        };
+
+        // 2 × 4 mm rectangle
+        assert_eq!(
+            volume.measure(&polygon),
+            Some(MeasurementValue::Area {
+                area: 8.0,
+                perimeter: 12.0
+            })
+        );
+        // Circle with a radius of 2 mm
+        let Some(MeasurementValue::Area { area, perimeter }) = volume.measure(&circle) else {
+            panic!("should have measured an area");
+        };
+        assert!((area - 4.0 * PI).abs() < 1e-4);
+        assert!((perimeter - 4.0 * PI).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_serialization_round_trip() {
+        let measurement = Measurement::Angle {
+            vertex: Point3::new(1.0, 2.0, 3.0),
+            first: Point3::new(2.0, 2.0, 3.0),
+            second: Point3::new(1.0, 3.0, 3.0),
+        };
+
+        let json = serde_json::to_string(&measurement).unwrap();
+
+        assert!(json.starts_with(r#"{"type":"angle","vertex":{"x":1.0"#));
+        assert_eq!(
+            serde_json::from_str::<Measurement>(&json).unwrap(),
+            measurement
+        );
+    }
+
+    #[test]
+    fn test_degenerate_measurements() {
+        let volume = test_volume();
+        let measurements = [
+            Measurement::Polyline {
+                points: vec![Point3::default()],
+            },
+            Measurement::Polygon {
+                points: vec![Point3::default(), Point3::new(1.0, 0.0, 0.0)],
+            },
+            Measurement::Angle {
+                vertex: Point3::default(),
+                first: Point3::default(),
+                second: Point3::new(1.0, 0.0, 0.0),
+            },
+        ];
+
+        assert!(measurements.iter().all(|m| volume.measure(m).is_none()));
+    }
+}
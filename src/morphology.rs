@@ -0,0 +1,341 @@
+use crate::enums::Connectivity;
+use crate::enums::Morphology;
+use crate::enums::StructuringElement;
+use crate::geometry::Point3;
+use crate::volume::Volume;
+
+use ndarray::Array3;
+use ndarray::Axis;
+use ndarray::Zip;
+
+impl Volume {
+    /// Get a new mask with a morphological operation applied to the
+    /// non-zero voxels, keeping the geometry
+    ///
+    /// The result is 1 inside and 0 outside. Voxels outside of the volume
+    /// are ignored, so objects touching the border are not eroded from it.
+    pub fn morphology(&self, operation: &Morphology) -> Volume {
+        let mask = self.data.mapv(|value| value != 0.0);
+        let result = match *operation {
+            Morphology::Erode { radius, element } => self.erode(&mask, radius, element),
+            Morphology::Dilate { radius, element } => self.dilate(&mask, radius, element),
+            Morphology::Open { radius, element } => {
+                self.dilate(&self.erode(&mask, radius, element), radius, element)
+            }
+            Morphology::Close { radius, element } => {
+                self.erode(&self.dilate(&mask, radius, element), radius, element)
+            }
+            Morphology::FillHoles => self.fill_holes(&mask),
+        };
+
+        let data = result.mapv(|inside| if inside { 1.0 } else { 0.0 });
+        Volume::new(data, self.spacing).with_geometry(self.origin, self.direction)
+    }
+
+    /// Keep voxels whose whole neighbourhood lies inside the mask
+    fn erode(&self, mask: &Array3<bool>, radius: f32, element: StructuringElement) -> Array3<bool> {
+        match element {
+            StructuringElement::Box => box_pass(mask, self.voxel_radius(radius), true),
+            // Sphere voxels are further than the radius from the background
+            StructuringElement::Sphere => {
+                let background = mask.mapv(|inside| !inside);
+                let distance = euclidean_distance(&background, self.axis_spacing());
+                let limit = sphere_limit(radius);
+                Zip::from(mask)
+                    .and(&distance)
+                    .par_map_collect(|&inside, &distance| inside && distance > limit)
+            }
+        }
+    }
+
+    /// Add voxels with any neighbour inside the mask
+    fn dilate(
+        &self,
+        mask: &Array3<bool>,
+        radius: f32,
+        element: StructuringElement,
+    ) -> Array3<bool> {
+        match element {
+            StructuringElement::Box => box_pass(mask, self.voxel_radius(radius), false),
+            StructuringElement::Sphere => {
+                let distance = euclidean_distance(mask, self.axis_spacing());
+                let limit = sphere_limit(radius);
+                distance.mapv(|distance| distance <= limit)
+            }
+        }
+    }
+
+    fn fill_holes(&self, mask: &Array3<bool>) -> Array3<bool> {
+        let background = self.threshold(0.0, 0.0);
+        let components = background.label_components(Connectivity::Face);
+        let (depth, height, width) = self.dim();
+        let last = Point3::new(width as f32 - 1.0, height as f32 - 1.0, depth as f32 - 1.0);
+        let holes = components.mask(|component| {
+            let touches = |min: f32, max: f32, last: f32| min == 0.0 || max == last;
+            !(touches(component.min.x, component.max.x, last.x)
+                || touches(component.min.y, component.max.y, last.y)
+                || touches(component.min.z, component.max.z, last.z))
+        });
+        Zip::from(mask)
+            .and(&holes.data)
+            .par_map_collect(|&inside, &hole| inside || hole != 0.0)
+    }
+}
+
+/// Lower envelope of the parabolas `weight · (p - q)² + f(q)`, the exact
+/// squared distance along one axis (Felzenszwalb and Huttenlocher)
+fn squared_distance_1d(f: &[f64], weight: f64, out: &mut [f64]) {
+    let height = |q: usize| f[q] + weight * (q * q) as f64;
+    // Parabola vertices and the left boundary of the part each one covers
+    let mut vertices: Vec<usize> = Vec::with_capacity(f.len());
+    let mut boundaries: Vec<f64> = Vec::with_capacity(f.len());
+
+    for q in (0..f.len()).filter(|&q| f[q].is_finite()) {
+        loop {
+            let Some(&last) = vertices.last() else {
+                vertices.push(q);
+                boundaries.push(f64::NEG_INFINITY);
+                break;
+            };
+            let s = (height(q) - height(last)) / (2.0 * weight * (q - last) as f64);
+            if s <= boundaries[boundaries.len() - 1] {
+                vertices.pop();
+                boundaries.pop();
+            } else {
+                vertices.push(q);
+                boundaries.push(s);
+                break;
+            }
+        }
+    }
+
+    if vertices.is_empty() {
+        out.fill(f64::INFINITY);
+        return;
+    }
+    let mut k = 0;
+    for (p, value) in out.iter_mut().enumerate() {
+        while k + 1 < vertices.len() && boundaries[k + 1] < p as f64 {
+            k += 1;
+        }
+        let q = vertices[k];
+        *value = weight * (p as f64 - q as f64).powi(2) + f[q];
+    }
+}
+
+/// Get the distance in mm from each voxel centre to the closest voxel centre
+/// of `foreground`, infinite without such voxels
+///
+/// `spacing` is given per array axis, see [`Volume::axis_spacing`].
+pub(crate) fn euclidean_distance(foreground: &Array3<bool>, spacing: [f32; 3]) -> Array3<f32> {
+    let mut squared =
+        Zip::from(foreground).par_map_collect(|&inside| if inside { 0.0 } else { f64::INFINITY });
+    for axis in (0..3).rev() {
+        let weight = (spacing[axis] as f64).powi(2);
+        Zip::from(squared.lanes_mut(Axis(axis))).par_for_each(|mut lane| {
+            let input = lane.to_vec();
+            let mut output = vec![0.0; input.len()];
+            squared_distance_1d(&input, weight, &mut output);
+            lane.iter_mut()
+                .zip(output)
+                .for_each(|(value, distance)| *value = distance);
+        });
+    }
+    squared.mapv(|value| value.sqrt() as f32)
+}
+
+/// Get the distance in mm up to which voxels belong to a sphere of `radius`
+/// mm, accepting rounding errors
+///
+/// Negative and NaN radii are 0, so the sphere is just the voxel itself.
+fn sphere_limit(radius: f32) -> f32 {
+    // `max` returns the other operand for NaN
+    radius.max(0.0) * (1.0 + 1e-3)
+}
+
+/// Erode (all voxels inside) or dilate (any voxel inside) a mask with a box
+/// of `radius` voxels per axis, one axis at a time
+fn box_pass(mask: &Array3<bool>, radius: [usize; 3], erode: bool) -> Array3<bool> {
+    let mut result = mask.clone();
+    for (axis, &radius) in radius.iter().enumerate() {
+        if radius == 0 {
+            continue;
+        }
+        Zip::from(result.lanes_mut(Axis(axis))).par_for_each(|mut lane| {
+            // Number of voxels deciding the result before each position, i.e.
+            // outside voxels for erosion and inside voxels for dilation
+            let mut counts = Vec::with_capacity(lane.len() + 1);
+            counts.push(0);
+            for &inside in lane.iter() {
+                counts.push(counts[counts.len() - 1] + usize::from(inside != erode));
+            }
+            let len = lane.len();
+            for (i, value) in lane.iter_mut().enumerate() {
+                let window = counts[(i + radius + 1).min(len)] - counts[i.saturating_sub(radius)];
+                *value = (window == 0) == erode;
+            }
+        });
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::box_offsets;
+    use crate::segmentation::neighbour;
+    use ndarray::s;
+
+    /// Sphere with a radius of 4 mm in 1 × 1 × 2 mm voxels
+    fn sphere() -> Volume {
+        let data = Array3::from_shape_fn((9, 17, 17), |(z, y, x)| {
+            let offset = Point3::new(x as f32 - 8.0, y as f32 - 8.0, (z as f32 - 4.0) * 2.0);
+            if offset.length() <= 4.0 { 1.0 } else { 0.0 }
+        });
+        Volume::new(data, (1.0, 1.0, 2.0))
+    }
+
+    #[test]
+    fn test_structuring_elements() {
+        let mut volume = Volume::new(Array3::zeros((9, 17, 17)), (1.0, 1.0, 2.0));
+        volume.data_mut()[[4, 8, 8]] = 1.0;
+        let dilate = |element| {
+            volume.morphology(&Morphology::Dilate {
+                radius: 2.0,
+                element,
+            })
+        };
+
+        let sphere = dilate(StructuringElement::Sphere);
+        let cube = dilate(StructuringElement::Box);
+
+        // 5 × 5 box, 3 slices deep
+        assert_eq!(cube.data.sum(), 75.0);
+        assert_eq!(cube.data.slice(s![3..6, 6..11, 6..11]).sum(), 75.0);
+        // 13 voxels in the central slice and one above and below
+        assert_eq!(sphere.data.sum(), 15.0);
+        assert_eq!(sphere.data[[3, 8, 8]], 1.0);
+    }
+
+    #[test]
+    fn test_erode_and_dilate_sphere() {
+        let volume = sphere();
+        let element = StructuringElement::Sphere;
+
+        let eroded = volume.morphology(&Morphology::Erode {
+            radius: 2.0,
+            element,
+        });
+        let dilated = volume.morphology(&Morphology::Dilate {
+            radius: 2.0,
+            element,
+        });
+
+        // Radii of 2 and 6 mm
+        assert_eq!(eroded.data[[4, 8, 10]], 1.0);
+        assert_eq!(eroded.data[[4, 8, 11]], 0.0);
+        assert_eq!(eroded.data[[3, 8, 8]], 1.0);
+        assert_eq!(eroded.data[[2, 8, 8]], 0.0);
+        assert_eq!(dilated.data[[4, 8, 14]], 1.0);
+        assert_eq!(dilated.data[[4, 8, 15]], 0.0);
+        assert_eq!(dilated.data[[1, 8, 8]], 1.0);
+        assert_eq!(dilated.data[[0, 8, 8]], 0.0);
+        assert!(eroded.data.sum() < volume.data.sum());
+        assert_eq!(dilated.spacing, volume.spacing);
+    }
+
+    #[test]
+    fn test_matches_brute_force() {
+        // Sphere with a notch in anisotropic voxels
+        let mut volume = Volume::new(sphere().data, (1.0, 1.5, 2.0));
+        volume.data_mut().slice_mut(s![4, 6..9, 11..]).fill(0.0);
+        let radius = 2.0;
+
+        for element in [StructuringElement::Box, StructuringElement::Sphere] {
+            let offsets: Vec<[isize; 3]> = box_offsets(volume.voxel_radius(radius))
+                .into_iter()
+                .filter(|offset| {
+                    let mm = Point3::new(offset[2] as f32, offset[1] as f32, offset[0] as f32)
+                        .scale(volume.spacing);
+                    matches!(element, StructuringElement::Box) || mm.length() <= radius * 1.001
+                })
+                .collect();
+            let eroded = volume.morphology(&Morphology::Erode { radius, element });
+            let dilated = volume.morphology(&Morphology::Dilate { radius, element });
+            assert!(eroded.data.sum() > 0.0);
+
+            for ((z, y, x), &value) in volume.data.indexed_iter() {
+                let neighbours = offsets.iter().filter_map(|&offset| {
+                    neighbour([z, y, x], offset, volume.data.shape())
+                        .map(|next| volume.data[next] != 0.0)
+                });
+                let all = value != 0.0 && neighbours.clone().all(|inside| inside);
+                let any = neighbours.clone().any(|inside| inside);
+                assert_eq!(eroded.data[[z, y, x]] != 0.0, all);
+                assert_eq!(dilated.data[[z, y, x]] != 0.0, any);
+            }
+        }
+    }
+
+    #[test]
+    fn test_non_positive_radius_is_identity() {
+        let volume = sphere();
+
+        for radius in [0.0, -1.0, f32::NAN] {
+            for element in [StructuringElement::Box, StructuringElement::Sphere] {
+                for operation in [
+                    Morphology::Erode { radius, element },
+                    Morphology::Dilate { radius, element },
+                ] {
+                    assert_eq!(volume.morphology(&operation).data, volume.data);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_open_and_close() {
+        let mut volume = Volume::new(Array3::zeros((5, 12, 12)), (1.0, 1.0, 1.0));
+        volume
+            .data_mut()
+            .slice_mut(s![1..4, 2..10, 2..10])
+            .fill(1.0);
+        // Speck to be removed by opening, gap to be closed by closing
+        volume.data_mut()[[2, 0, 0]] = 1.0;
+        volume.data_mut().slice_mut(s![1..4, 2..10, 6]).fill(0.0);
+        let element = StructuringElement::Box;
+
+        let opened = volume.morphology(&Morphology::Open {
+            radius: 1.0,
+            element,
+        });
+        let closed = volume.morphology(&Morphology::Close {
+            radius: 1.0,
+            element,
+        });
+
+        assert_eq!(opened.data[[2, 0, 0]], 0.0);
+        assert_eq!(opened.data[[2, 4, 4]], 1.0);
+        assert_eq!(closed.data[[2, 5, 6]], 1.0);
+        assert_eq!(closed.data[[2, 0, 0]], 1.0);
+        assert_eq!(closed.data[[2, 5, 11]], 0.0);
+    }
+
+    #[test]
+    fn test_fill_holes() {
+        let mut volume = Volume::new(Array3::zeros((7, 7, 7)), (1.0, 1.0, 1.0));
+        volume.data_mut().slice_mut(s![1..6, 1..6, 1..6]).fill(1.0);
+        volume.data_mut().slice_mut(s![2..5, 2..5, 2..5]).fill(0.0);
+        // Cavity opened to the outside along x
+        volume.data_mut().slice_mut(s![3, 3, 0..3]).fill(0.0);
+        let mut closed = Volume::new(volume.data.clone(), (1.0, 1.0, 1.0));
+        closed.data_mut()[[3, 3, 1]] = 1.0;
+
+        let open = volume.morphology(&Morphology::FillHoles);
+        let filled = closed.morphology(&Morphology::FillHoles);
+
+        assert_eq!(open.data, volume.data);
+        assert_eq!(filled.data.sum(), 125.0);
+        assert_eq!(filled.data[[0, 0, 0]], 0.0);
+    }
+}
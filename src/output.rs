@@ -0,0 +1,187 @@
+use crate::enums::Interpolation;
+use crate::enums::Orientation;
+use crate::volume::Volume;
+
+use image::ImageBuffer;
+use image::Luma;
+use ndarray::Array2;
+use ndarray::ArrayView2;
+
+/// Linear mapping of intensities to 16-bit pixels, `value * slope + intercept`
+///
+/// Results are rounded and clamped to the 16-bit range. The default mapping
+/// keeps the intensities unchanged.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LinearMapping {
+    pub slope: f32,
+    pub intercept: f32,
+}
+
+impl Default for LinearMapping {
+    fn default() -> Self {
+        Self::new(1.0, 0.0)
+    }
+}
+
+impl LinearMapping {
+    pub fn new(slope: f32, intercept: f32) -> Self {
+        Self { slope, intercept }
+    }
+
+    /// Map `lower` to 0 and `upper` to 65535
+    pub fn window(lower: f32, upper: f32) -> Self {
+        let slope = 65535.0 / (upper - lower);
+        Self::new(slope, -lower * slope)
+    }
+
+    #[inline]
+    pub fn to_u16(&self, value: f32) -> u16 {
+        value
+            .mul_add(self.slope, self.intercept)
+            .round()
+            .clamp(0.0, 65535.0) as u16
+    }
+
+    /// Convert a plane of intensities to a 16-bit image
+    pub fn to_u16_image(
+        &self,
+        values: &ArrayView2<'_, f32>,
+    ) -> Option<ImageBuffer<Luma<u16>, Vec<u16>>> {
+        let (height, width) = values.dim();
+        let pixels = values.iter().map(|&value| self.to_u16(value)).collect();
+        ImageBuffer::from_raw(width as u32, height as u32, pixels)
+    }
+}
+
+/// Convert a plane of intensities to a float image without any mapping
+pub fn to_f32_image(values: &ArrayView2<'_, f32>) -> Option<ImageBuffer<Luma<f32>, Vec<f32>>> {
+    let (height, width) = values.dim();
+    ImageBuffer::from_raw(
+        width as u32,
+        height as u32,
+        values.iter().copied().collect(),
+    )
+}
+
+impl Volume {
+    /// Get a slice resampled like [`Volume::get_image_from_axis`], without
+    /// quantisation
+    pub fn get_resampled_slice_from_axis(
+        &self,
+        index: usize,
+        orientation: Orientation,
+        interpolation: Interpolation,
+    ) -> Option<Array2<f32>> {
+        let slice = self.get_slice_from_axis(index, &orientation)?;
+        Some(
+            self.stretch_plane(slice, orientation, &interpolation)
+                .into_owned(),
+        )
+    }
+
+    /// Get a slice as 16-bit image
+    pub fn get_image_u16_from_axis(
+        &self,
+        index: usize,
+        orientation: Orientation,
+        interpolation: Interpolation,
+        mapping: &LinearMapping,
+    ) -> Option<ImageBuffer<Luma<u16>, Vec<u16>>> {
+        let slice = self.get_slice_from_axis(index, &orientation)?;
+        let plane = self.stretch_plane(slice, orientation, &interpolation);
+        mapping.to_u16_image(&plane.view())
+    }
+
+    /// Get a slice as float image
+    pub fn get_image_f32_from_axis(
+        &self,
+        index: usize,
+        orientation: Orientation,
+        interpolation: Interpolation,
+    ) -> Option<ImageBuffer<Luma<f32>, Vec<f32>>> {
+        let slice = self.get_slice_from_axis(index, &orientation)?;
+        let plane = self.stretch_plane(slice, orientation, &interpolation);
+        to_f32_image(&plane.view())
+    }
+
+    /// Get a slice at a fractional index as 16-bit image
+    pub fn get_image_u16_at_position(
+        &self,
+        position: f32,
+        orientation: Orientation,
+        interpolation: Interpolation,
+        mapping: &LinearMapping,
+    ) -> Option<ImageBuffer<Luma<u16>, Vec<u16>>> {
+        let slice = self.get_slice_at_position(position, orientation, interpolation)?;
+        mapping.to_u16_image(&slice.view())
+    }
+
+    /// Get a slice at a fractional index as float image
+    pub fn get_image_f32_at_position(
+        &self,
+        position: f32,
+        orientation: Orientation,
+        interpolation: Interpolation,
+    ) -> Option<ImageBuffer<Luma<f32>, Vec<f32>>> {
+        let slice = self.get_slice_at_position(position, orientation, interpolation)?;
+        to_f32_image(&slice.view())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::enums::Processor;
+    use ndarray::Array3;
+
+    fn test_volume() -> Volume {
+        // Intensity encodes the voxel index, all values are below one 8-bit step
+        let data = Array3::from_shape_fn((3, 4, 5), |(z, y, x)| (z * 100 + y * 10 + x) as f32);
+        Volume::new(data, (1.0, 1.0, 2.0))
+    }
+
+    #[test]
+    fn test_u16_image_keeps_precision() {
+        let volume = test_volume();
+
+        let image = volume
+            .get_image_u16_from_axis(
+                1,
+                Orientation::Axial,
+                Interpolation::None,
+                &LinearMapping::default(),
+            )
+            .unwrap();
+
+        assert_eq!(image.dimensions(), (5, 4));
+        assert_eq!(image.get_pixel(3, 2).0, [123]);
+    }
+
+    #[test]
+    fn test_window_mapping() {
+        let mapping = LinearMapping::window(100.0, 200.0);
+
+        assert_eq!(mapping.to_u16(50.0), 0);
+        assert_eq!(mapping.to_u16(150.0), 32768);
+        assert_eq!(mapping.to_u16(200.0), 65535);
+    }
+
+    #[test]
+    fn test_f32_image_matches_resampled_slice() {
+        let volume = test_volume();
+        let interpolation = Interpolation::Bilinear(Processor::CPU);
+
+        let slice = volume
+            .get_resampled_slice_from_axis(2, Orientation::Coronal, interpolation.clone())
+            .unwrap();
+        let image = volume
+            .get_image_f32_from_axis(2, Orientation::Coronal, interpolation)
+            .unwrap();
+
+        // Depth is stretched from 3 slices at 2 mm to 6 isotropic rows
+        assert_eq!(slice.dim(), (6, 5));
+        assert_eq!(image.dimensions(), (5, 6));
+        assert_eq!(image.get_pixel(0, 5).0, [slice[[5, 0]]]);
+        assert_eq!(slice[[5, 0]], 220.0);
+    }
+}
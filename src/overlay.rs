@@ -0,0 +1,252 @@
+use crate::enums::Interpolation;
+use crate::enums::Orientation;
+use crate::enums::OverlayStyle;
+use crate::volume::Volume;
+
+use image::ImageBuffer;
+use image::Rgb;
+use ndarray::Array2;
+use std::collections::BTreeMap;
+
+/// Colours of labels missing in the colour table, cycled by label value
+const DEFAULT_COLORS: [[u8; 3]; 8] = [
+    [230, 25, 75],
+    [60, 180, 75],
+    [255, 225, 25],
+    [0, 130, 200],
+    [245, 130, 48],
+    [145, 30, 180],
+    [70, 240, 240],
+    [240, 50, 230],
+];
+
+/// Appearance of a label volume drawn over a slice
+///
+/// Label 0 is background and never drawn.
+#[derive(Clone, Debug)]
+pub struct LabelOverlay {
+    pub colors: BTreeMap<u32, [u8; 3]>,
+    pub style: OverlayStyle,
+    /// Opacity of filled regions in 0..=1, outlines are opaque
+    pub opacity: f32,
+}
+
+impl Default for LabelOverlay {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LabelOverlay {
+    pub fn new() -> Self {
+        Self {
+            colors: BTreeMap::new(),
+            style: OverlayStyle::default(),
+            opacity: 0.4,
+        }
+    }
+
+    pub fn with_color(mut self, label: u32, color: [u8; 3]) -> Self {
+        self.colors.insert(label, color);
+        self
+    }
+
+    pub fn with_style(mut self, style: OverlayStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    pub fn with_opacity(mut self, opacity: f32) -> Self {
+        self.opacity = opacity;
+        self
+    }
+
+    /// Get the colour of a label
+    pub fn color(&self, label: u32) -> [u8; 3] {
+        self.colors
+            .get(&label)
+            .copied()
+            .unwrap_or(DEFAULT_COLORS[(label as usize).wrapping_sub(1) % DEFAULT_COLORS.len()])
+    }
+
+    /// Whether a label differs from one of its 4-neighbours
+    fn is_boundary(labels: &Array2<u32>, row: usize, col: usize) -> bool {
+        let (height, width) = labels.dim();
+        let label = labels[[row, col]];
+        (row > 0 && labels[[row - 1, col]] != label)
+            || (row + 1 < height && labels[[row + 1, col]] != label)
+            || (col > 0 && labels[[row, col - 1]] != label)
+            || (col + 1 < width && labels[[row, col + 1]] != label)
+    }
+
+    /// Draw the labels over the grey pixels of a plane in row-major order
+    fn draw(&self, grey: &[u8], labels: &Array2<u32>) -> Vec<u8> {
+        let (fill, outline) = match self.style {
+            OverlayStyle::Fill => (true, false),
+            OverlayStyle::Outline => (false, true),
+            OverlayStyle::FillAndOutline => (true, true),
+        };
+        let opacity = self.opacity.clamp(0.0, 1.0);
+
+        let mut pixels = Vec::with_capacity(grey.len() * 3);
+        for ((index, &label), &value) in labels.indexed_iter().zip(grey) {
+            let base = [value; 3];
+            if label == 0 {
+                pixels.extend(base);
+                continue;
+            }
+            let color = self.color(label);
+            let pixel = if outline && Self::is_boundary(labels, index.0, index.1) {
+                color
+            } else if fill {
+                std::array::from_fn(|c| {
+                    (base[c] as f32 * (1.0 - opacity) + color[c] as f32 * opacity).round() as u8
+                })
+            } else {
+                base
+            };
+            pixels.extend(pixel);
+        }
+        pixels
+    }
+}
+
+impl Volume {
+    /// Get a slice as image with a label volume of the same geometry drawn
+    /// on top
+    ///
+    /// The labels are stretched with nearest neighbour interpolation to the
+    /// same plane as the grey image. Returns `None` if the dimensions of the
+    /// volumes differ.
+    pub fn get_overlay_image_from_axis(
+        &self,
+        index: usize,
+        orientation: Orientation,
+        interpolation: Interpolation,
+        labels: &Volume,
+        overlay: &LabelOverlay,
+    ) -> Option<ImageBuffer<Rgb<u8>, Vec<u8>>> {
+        if labels.dim() != self.dim() {
+            return None;
+        }
+
+        let label_interpolation = match &interpolation {
+            Interpolation::None => Interpolation::None,
+            other => Interpolation::NearestNeighbor(other.processor().clone()),
+        };
+        let slice = self.get_slice_from_axis(index, &orientation)?;
+        let plane = self.stretch_plane(slice, orientation, &interpolation);
+        let label_slice = labels.get_slice_from_axis(index, &orientation)?;
+        let label_plane = self
+            .stretch_plane(label_slice, orientation, &label_interpolation)
+            .map(|&label| label.round().max(0.0) as u32);
+
+        let grey = interpolation.processor().backend().to_u8(&plane.view());
+        let (height, width) = plane.dim();
+        ImageBuffer::from_raw(
+            width as u32,
+            height as u32,
+            overlay.draw(&grey, &label_plane),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::enums::Processor;
+    use ndarray::Array3;
+    use ndarray::s;
+
+    fn test_volumes() -> (Volume, Volume) {
+        let image = Volume::new(Array3::zeros((4, 6, 6)), (1.0, 1.0, 2.0));
+        let mut labels = Array3::zeros((4, 6, 6));
+        labels.slice_mut(s![.., 1..5, 1..5]).fill(2.0);
+        (image, Volume::new(labels, (1.0, 1.0, 2.0)))
+    }
+
+    #[test]
+    fn test_fill_and_outline() {
+        let (image, labels) = test_volumes();
+        let overlay = LabelOverlay::new()
+            .with_color(2, [0, 200, 0])
+            .with_opacity(0.5);
+
+        let result = image
+            .get_overlay_image_from_axis(
+                1,
+                Orientation::Axial,
+                Interpolation::None,
+                &labels,
+                &overlay,
+            )
+            .unwrap();
+
+        assert_eq!(result.get_pixel(0, 0).0, [0, 0, 0]);
+        assert_eq!(result.get_pixel(1, 1).0, [0, 200, 0]);
+        assert_eq!(result.get_pixel(2, 2).0, [0, 100, 0]);
+    }
+
+    #[test]
+    fn test_outline_only() {
+        let (image, labels) = test_volumes();
+        let overlay = LabelOverlay::new().with_style(OverlayStyle::Outline);
+
+        let result = image
+            .get_overlay_image_from_axis(
+                1,
+                Orientation::Axial,
+                Interpolation::None,
+                &labels,
+                &overlay,
+            )
+            .unwrap();
+
+        assert_eq!(result.get_pixel(1, 3).0, DEFAULT_COLORS[1]);
+        assert_eq!(result.get_pixel(2, 2).0, [0, 0, 0]);
+    }
+
+    #[test]
+    fn test_interpolated_coronal_overlay() {
+        let (image, labels) = test_volumes();
+        let overlay = LabelOverlay::new()
+            .with_style(OverlayStyle::Fill)
+            .with_opacity(1.0);
+
+        let result = image
+            .get_overlay_image_from_axis(
+                2,
+                Orientation::Coronal,
+                Interpolation::Trilinear(Processor::CPU),
+                &labels,
+                &overlay,
+            )
+            .unwrap();
+
+        // Depth is stretched from 4 slices at 2 mm to 8 isotropic rows
+        assert_eq!(result.height(), 8);
+        assert!(
+            result
+                .rows()
+                .all(|mut row| row.nth(2).unwrap().0 == DEFAULT_COLORS[1])
+        );
+    }
+
+    #[test]
+    fn test_rejects_mismatching_labels() {
+        let (image, _) = test_volumes();
+        let labels = Volume::new(Array3::zeros((4, 6, 5)), (1.0, 1.0, 2.0));
+
+        assert!(
+            image
+                .get_overlay_image_from_axis(
+                    0,
+                    Orientation::Axial,
+                    Interpolation::None,
+                    &labels,
+                    &LabelOverlay::new()
+                )
+                .is_none()
+        );
+    }
+}
@@ -0,0 +1,209 @@
+use crate::enums::Interpolation;
+use crate::enums::Orientation;
+use crate::enums::Processor;
+use crate::enums::ProjectionMode;
+use crate::volume::Volume;
+
+use image::ImageBuffer;
+use image::Luma;
+use ndarray::Array2;
+use ndarray::ArrayView1;
+use ndarray::Axis;
+use ndarray::s;
+
+impl ProjectionMode {
+    #[inline]
+    pub(crate) fn reduce(&self, values: ArrayView1<'_, f32>) -> f32 {
+        match self {
+            ProjectionMode::Maximum => values.fold(f32::NEG_INFINITY, |acc, &v| acc.max(v)),
+            ProjectionMode::Minimum => values.fold(f32::INFINITY, |acc, &v| acc.min(v)),
+            ProjectionMode::Mean => values.sum() / values.len() as f32,
+            ProjectionMode::Sum => values.sum(),
+        }
+    }
+}
+
+impl Volume {
+    /// Get the range of slices covered by a slab of `thickness` mm centred
+    /// on `index`. At least one slice is always included.
+    pub(crate) fn get_slab_range(
+        &self,
+        index: usize,
+        thickness: f32,
+        orientation: &Orientation,
+    ) -> Option<(usize, usize)> {
+        let len = self.data.len_of(Axis(orientation.axis()));
+        if index >= len {
+            return None;
+        }
+
+        let slices = (thickness / self.get_slice_spacing(orientation))
+            .round()
+            .max(1.0) as usize;
+        let start = index.saturating_sub((slices - 1) / 2);
+        let end = (start + slices).min(len);
+        Some((start, end))
+    }
+
+    /// Get a thick-slab projection of `thickness` mm centred on `index`
+    pub fn get_projection_from_axis(
+        &self,
+        index: usize,
+        thickness: f32,
+        orientation: Orientation,
+        mode: ProjectionMode,
+        processor: &Processor,
+    ) -> Option<Array2<f32>> {
+        let (start, end) = self.get_slab_range(index, thickness, &orientation)?;
+        let slab = match orientation {
+            Orientation::Axial => self.data.slice(s![start..end, .., ..]),
+            Orientation::Coronal => self.data.slice(s![.., start..end, ..]),
+            Orientation::Sagittal => self.data.slice(s![.., .., start..end]),
+        };
+        Some(processor.backend().project(&slab, orientation.axis(), mode))
+    }
+
+    /// Get a projection through the full volume
+    pub fn get_full_projection(
+        &self,
+        orientation: Orientation,
+        mode: ProjectionMode,
+        processor: &Processor,
+    ) -> Option<Array2<f32>> {
+        if self.data.is_empty() {
+            return None;
+        }
+        Some(
+            processor
+                .backend()
+                .project(&self.data.view(), orientation.axis(), mode),
+        )
+    }
+
+    /// Get a thick-slab projection of `thickness` mm centred on `index` as image
+    pub fn get_projection_image_from_axis(
+        &self,
+        index: usize,
+        thickness: f32,
+        orientation: Orientation,
+        mode: ProjectionMode,
+        interpolation: Interpolation,
+    ) -> Option<ImageBuffer<Luma<u8>, Vec<u8>>> {
+        let projection = self.get_projection_from_axis(
+            index,
+            thickness,
+            orientation,
+            mode,
+            interpolation.processor(),
+        )?;
+        self.plane_to_image(&projection.view(), orientation, &interpolation)
+    }
+
+    /// Get a projection through the full volume as image
+    pub fn get_full_projection_image(
+        &self,
+        orientation: Orientation,
+        mode: ProjectionMode,
+        interpolation: Interpolation,
+    ) -> Option<ImageBuffer<Luma<u8>, Vec<u8>>> {
+        let projection = self.get_full_projection(orientation, mode, interpolation.processor())?;
+        self.plane_to_image(&projection.view(), orientation, &interpolation)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::Array3;
+
+    fn test_volume() -> Volume {
+        // Intensity equals the slice index
+        let data = Array3::from_shape_fn((5, 2, 3), |(z, _, _)| z as f32);
+        Volume::new(data, (1.0, 1.0, 2.0))
+    }
+
+    #[test]
+    fn test_full_axial_projection_modes() {
+        let volume = test_volume();
+
+        let max = volume
+            .get_full_projection(Orientation::Axial, ProjectionMode::Maximum, &Processor::CPU)
+            .unwrap();
+        let min = volume
+            .get_full_projection(Orientation::Axial, ProjectionMode::Minimum, &Processor::CPU)
+            .unwrap();
+        let mean = volume
+            .get_full_projection(Orientation::Axial, ProjectionMode::Mean, &Processor::CPU)
+            .unwrap();
+        let sum = volume
+            .get_full_projection(Orientation::Axial, ProjectionMode::Sum, &Processor::CPU)
+            .unwrap();
+
+        assert_eq!(max.dim(), (2, 3));
+        assert!(max.iter().all(|&v| v == 4.0));
+        assert!(min.iter().all(|&v| v == 0.0));
+        assert!(mean.iter().all(|&v| v == 2.0));
+        assert!(sum.iter().all(|&v| v == 10.0));
+    }
+
+    #[test]
+    fn test_slab_thickness_in_mm() {
+        let volume = test_volume();
+
+        // 6 mm at 2 mm slice spacing covers slices 1..4
+        let max = volume
+            .get_projection_from_axis(
+                2,
+                6.0,
+                Orientation::Axial,
+                ProjectionMode::Maximum,
+                &Processor::CPU,
+            )
+            .unwrap();
+        let min = volume
+            .get_projection_from_axis(
+                2,
+                6.0,
+                Orientation::Axial,
+                ProjectionMode::Minimum,
+                &Processor::CPU,
+            )
+            .unwrap();
+
+        assert!(max.iter().all(|&v| v == 3.0));
+        assert!(min.iter().all(|&v| v == 1.0));
+    }
+
+    #[test]
+    fn test_coronal_projection_shape() {
+        let volume = test_volume();
+
+        let result = volume
+            .get_projection_from_axis(
+                0,
+                10.0,
+                Orientation::Coronal,
+                ProjectionMode::Mean,
+                &Processor::CPU,
+            )
+            .unwrap();
+
+        assert_eq!(result.dim(), (5, 3));
+        assert_eq!(result[[3, 1]], 3.0);
+    }
+
+    #[test]
+    fn test_slab_index_out_of_bounds() {
+        let volume = test_volume();
+
+        let result = volume.get_projection_from_axis(
+            5,
+            2.0,
+            Orientation::Axial,
+            ProjectionMode::Maximum,
+            &Processor::CPU,
+        );
+
+        assert!(result.is_none());
+    }
+}
@@ -0,0 +1,349 @@
+use crate::enums::Connectivity;
+use crate::enums::GrowingCriterion;
+use crate::filter::box_offsets;
+use crate::geometry::Point3;
+use crate::segmentation::neighbour;
+use crate::volume::Volume;
+
+use ndarray::Array3;
+use std::sync::Arc;
+use thiserror::Error;
+
+/// Number of visited voxels between two calls of the cancellation hook
+const CANCEL_CHECK_INTERVAL: usize = 4096;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum RegionGrowingError {
+    #[error("No seed given")]
+    NoSeeds,
+
+    #[error("Seed {0:?} lies outside of the volume")]
+    SeedOutOfBounds(Point3),
+
+    #[error("Region growing was cancelled")]
+    Cancelled,
+}
+
+/// Parameters of [`Volume::grow_region`]
+#[derive(Clone)]
+pub struct RegionGrowing {
+    pub criterion: GrowingCriterion,
+    /// Neighbourhood through which the region grows, 6 neighbours by default
+    pub connectivity: Connectivity,
+    /// Maximum distance in mm from the closest seed
+    pub max_radius: Option<f32>,
+    cancel: Option<Arc<dyn Fn() -> bool + Send + Sync>>,
+}
+
+impl RegionGrowing {
+    pub fn new(criterion: GrowingCriterion) -> Self {
+        Self {
+            criterion,
+            connectivity: Connectivity::Face,
+            max_radius: None,
+            cancel: None,
+        }
+    }
+
+    pub fn with_connectivity(mut self, connectivity: Connectivity) -> Self {
+        self.connectivity = connectivity;
+        self
+    }
+
+    pub fn with_max_radius(mut self, max_radius: f32) -> Self {
+        self.max_radius = Some(max_radius);
+        self
+    }
+
+    /// Set a hook polled while growing, returning true aborts with
+    /// [`RegionGrowingError::Cancelled`]
+    pub fn with_cancel<F>(mut self, cancel: F) -> Self
+    where
+        F: Fn() -> bool + Send + Sync + 'static,
+    {
+        self.cancel = Some(Arc::new(cancel));
+        self
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancel.as_ref().is_some_and(|cancel| cancel())
+    }
+}
+
+/// Get the mean and population standard deviation of a set of values
+fn mean_std(values: &[f32]) -> (f32, f32) {
+    let count = values.len() as f64;
+    let mean = values.iter().map(|&v| v as f64).sum::<f64>() / count;
+    let variance = values
+        .iter()
+        .map(|&v| (v as f64 - mean).powi(2))
+        .sum::<f64>()
+        / count;
+    (mean as f32, variance.sqrt() as f32)
+}
+
+impl Volume {
+    /// Grow a region from seed voxels
+    ///
+    /// Seeds are voxel coordinates, rounded to the closest voxel. Seeds not
+    /// meeting the criterion are ignored. Returns a mask aligned with the
+    /// volume, 1 inside the region and 0 elsewhere.
+    ///
+    /// # Errors
+    ///
+    /// Fails without seeds, with a seed outside of the volume or when the
+    /// cancellation hook returns true.
+    pub fn grow_region(
+        &self,
+        seeds: &[Point3],
+        options: &RegionGrowing,
+    ) -> Result<Volume, RegionGrowingError> {
+        if seeds.is_empty() {
+            return Err(RegionGrowingError::NoSeeds);
+        }
+        let (depth, height, width) = self.dim();
+        let seeds = seeds
+            .iter()
+            .map(|&seed| {
+                let index = [seed.z, seed.y, seed.x].map(|v| v.round());
+                let inside = index
+                    .iter()
+                    .zip([depth, height, width])
+                    .all(|(&v, len)| v >= 0.0 && v < len as f32);
+                inside
+                    .then(|| index.map(|v| v as usize))
+                    .ok_or(RegionGrowingError::SeedOutOfBounds(seed))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let region = match options.criterion {
+            GrowingCriterion::Interval { lower, upper } => self.grow(&seeds, options, |index| {
+                (lower..=upper).contains(&self.data[index])
+            })?,
+            GrowingCriterion::ConfidenceConnected {
+                multiplier,
+                iterations,
+            } => self.grow_confidence_connected(&seeds, options, multiplier, iterations)?,
+            GrowingCriterion::Neighborhood {
+                lower,
+                upper,
+                radius,
+            } => {
+                let offsets = box_offsets(self.voxel_radius(radius));
+                let shape = self.data.shape();
+                self.grow(&seeds, options, |index| {
+                    offsets.iter().all(|&offset| {
+                        neighbour(index, offset, shape)
+                            .is_none_or(|next| (lower..=upper).contains(&self.data[next]))
+                    })
+                })?
+            }
+        };
+
+        let data = region.mapv(|inside| if inside { 1.0 } else { 0.0 });
+        Ok(Volume::new(data, self.spacing).with_geometry(self.origin, self.direction))
+    }
+
+    fn grow_confidence_connected(
+        &self,
+        seeds: &[[usize; 3]],
+        options: &RegionGrowing,
+        multiplier: f32,
+        iterations: usize,
+    ) -> Result<Array3<bool>, RegionGrowingError> {
+        let shape = self.data.shape();
+        let mut values: Vec<f32> = seeds
+            .iter()
+            .flat_map(|&seed| {
+                box_offsets([1; 3])
+                    .into_iter()
+                    .filter_map(move |offset| neighbour(seed, offset, shape))
+            })
+            .map(|index| self.data[index])
+            .collect();
+
+        let mut region = Array3::from_elem(self.data.raw_dim(), false);
+        for _ in 0..=iterations {
+            let (mean, std) = mean_std(&values);
+            let (lower, upper) = (mean - multiplier * std, mean + multiplier * std);
+            let grown = self.grow(seeds, options, |index| {
+                (lower..=upper).contains(&self.data[index])
+            })?;
+
+            values = self
+                .data
+                .iter()
+                .zip(&grown)
+                .filter(|&(_, &inside)| inside)
+                .map(|(&value, _)| value)
+                .collect();
+            region = grown;
+            if values.is_empty() {
+                break;
+            }
+        }
+        Ok(region)
+    }
+
+    /// Flood fill from the seeds through the voxels accepted by `accept`
+    fn grow<F>(
+        &self,
+        seeds: &[[usize; 3]],
+        options: &RegionGrowing,
+        accept: F,
+    ) -> Result<Array3<bool>, RegionGrowingError>
+    where
+        F: Fn([usize; 3]) -> bool,
+    {
+        let shape = self.data.shape();
+        let offsets = options.connectivity.offsets();
+        let within_radius = |index: [usize; 3]| {
+            let Some(max_radius) = options.max_radius else {
+                return true;
+            };
+            let point = Point3::new(index[2] as f32, index[1] as f32, index[0] as f32);
+            seeds.iter().any(|seed| {
+                let seed = Point3::new(seed[2] as f32, seed[1] as f32, seed[0] as f32);
+                (point - seed).scale(self.spacing).length() <= max_radius
+            })
+        };
+
+        let mut region = Array3::from_elem(self.data.raw_dim(), false);
+        let mut stack = Vec::new();
+        for &seed in seeds {
+            if !region[seed] && accept(seed) {
+                region[seed] = true;
+                stack.push(seed);
+            }
+        }
+
+        let mut visited = 0usize;
+        while let Some(index) = stack.pop() {
+            if visited.is_multiple_of(CANCEL_CHECK_INTERVAL) && options.is_cancelled() {
+                return Err(RegionGrowingError::Cancelled);
+            }
+            visited += 1;
+            for &offset in &offsets {
+                if let Some(next) = neighbour(index, offset, shape)
+                    && !region[next]
+                    && within_radius(next)
+                    && accept(next)
+                {
+                    region[next] = true;
+                    stack.push(next);
+                }
+            }
+        }
+        Ok(region)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::s;
+
+    /// Tube of 3 × 3 voxels along x at intensities 100..=102 with a single
+    /// outlier of 110, and a separate bright blob in the corner, in
+    /// 1 × 1 × 2 mm voxels
+    fn test_volume() -> Volume {
+        let mut data = Array3::from_shape_fn((10, 10, 20), |(z, y, x)| {
+            if (3..6).contains(&z) && (3..6).contains(&y) {
+                100.0 + (x % 3) as f32
+            } else {
+                ((x + y + z) % 3) as f32
+            }
+        });
+        data[[4, 4, 7]] = 110.0;
+        data.slice_mut(s![0..2, 0..2, 0..2]).fill(100.0);
+        Volume::new(data, (1.0, 1.0, 2.0))
+    }
+
+    /// Seed in the centre of the tube
+    fn seed() -> Vec<Point3> {
+        vec![Point3::new(10.0, 4.0, 4.0)]
+    }
+
+    #[test]
+    fn test_interval_growing_with_max_radius() {
+        let volume = test_volume();
+        let options = RegionGrowing::new(GrowingCriterion::Interval {
+            lower: 90.0,
+            upper: 120.0,
+        });
+
+        let tube = volume.grow_region(&seed(), &options).unwrap();
+        let limited = volume
+            .grow_region(&seed(), &options.clone().with_max_radius(3.0))
+            .unwrap();
+
+        assert_eq!(tube.data.sum(), 180.0);
+        assert_eq!(tube.data[[0, 0, 0]], 0.0);
+        assert_eq!(tube.spacing, volume.spacing);
+        // 17 voxels in the seed slice and 15 in each neighbouring slice 2 mm away
+        assert_eq!(limited.data.sum(), 47.0);
+    }
+
+    #[test]
+    fn test_confidence_connected() {
+        let volume = test_volume();
+        let options = RegionGrowing::new(GrowingCriterion::ConfidenceConnected {
+            multiplier: 2.5,
+            iterations: 2,
+        });
+
+        let region = volume.grow_region(&seed(), &options).unwrap();
+
+        // Mean 101 and std 0.8 around the seed exclude the outlier
+        assert_eq!(region.data.sum(), 179.0);
+        assert_eq!(region.data[[4, 4, 7]], 0.0);
+        assert_eq!(region.data[[0, 0, 0]], 0.0);
+    }
+
+    #[test]
+    fn test_neighborhood_criterion() {
+        let volume = test_volume();
+        let options = RegionGrowing::new(GrowingCriterion::Neighborhood {
+            lower: 90.0,
+            upper: 105.0,
+            radius: 1.0,
+        });
+
+        let region = volume.grow_region(&seed(), &options).unwrap();
+
+        // The box of 1 mm spans 3 × 3 voxels in-plane and a single slice, so
+        // only the central row of the tube qualifies, except next to the outlier
+        assert_eq!(region.data.sum(), 57.0);
+        assert_eq!(region.data[[4, 4, 8]], 0.0);
+        assert_eq!(region.data[[3, 4, 7]], 1.0);
+        assert_eq!(region.data[[4, 3, 10]], 0.0);
+    }
+
+    #[test]
+    fn test_errors_and_cancellation() {
+        let volume = test_volume();
+        let options = RegionGrowing::new(GrowingCriterion::Interval {
+            lower: 90.0,
+            upper: 120.0,
+        });
+
+        assert_eq!(
+            volume.grow_region(&[], &options).err(),
+            Some(RegionGrowingError::NoSeeds)
+        );
+        assert_eq!(
+            volume
+                .grow_region(&[Point3::new(20.0, 0.0, 0.0)], &options)
+                .err(),
+            Some(RegionGrowingError::SeedOutOfBounds(Point3::new(
+                20.0, 0.0, 0.0
+            )))
+        );
+        assert_eq!(
+            volume
+                .grow_region(&seed(), &options.with_cancel(|| true))
+                .err(),
+            Some(RegionGrowingError::Cancelled)
+        );
+    }
+}
@@ -0,0 +1,523 @@
+use crate::enums::Interpolation;
+use crate::enums::RenderMode;
+use crate::geometry::Point3;
+use crate::interpolator::Interpolator;
+use crate::volume::Volume;
+
+use image::ImageBuffer;
+use image::Rgba;
+use ndarray::ArrayView3;
+use rayon::iter::IntoParallelIterator;
+use rayon::iter::ParallelIterator;
+
+/// Edge length of the image tiles rendered in parallel
+const TILE_SIZE: u32 = 32;
+/// Accumulated opacity at which compositing stops
+const OPACITY_CUTOFF: f32 = 0.99;
+
+const AMBIENT: f32 = 0.2;
+const DIFFUSE: f32 = 0.7;
+const SPECULAR: f32 = 0.3;
+const SHININESS: i32 = 20;
+
+#[derive(Clone, Copy)]
+pub enum CameraProjection {
+    /// Parallel rays, showing `height` mm of the scene vertically
+    Orthographic { height: f32 },
+    /// Rays through the eye with a vertical field of view in degrees
+    Perspective { fov: f32 },
+}
+
+/// Camera in patient coordinates (mm)
+#[derive(Clone, Copy)]
+pub struct Camera {
+    pub eye: Point3,
+    pub target: Point3,
+    pub up: Point3,
+    pub projection: CameraProjection,
+}
+
+impl Camera {
+    pub fn orthographic(eye: Point3, target: Point3, height: f32) -> Self {
+        Self {
+            eye,
+            target,
+            up: Point3::new(0.0, 0.0, 1.0),
+            projection: CameraProjection::Orthographic { height },
+        }
+    }
+
+    pub fn perspective(eye: Point3, target: Point3, fov: f32) -> Self {
+        Self {
+            eye,
+            target,
+            up: Point3::new(0.0, 0.0, 1.0),
+            projection: CameraProjection::Perspective { fov },
+        }
+    }
+
+    pub fn with_up(mut self, up: Point3) -> Self {
+        self.up = up;
+        self
+    }
+
+    /// Get the unit (right, up, forward) vectors of the view, `None` if
+    /// the eye coincides with the target or looks along the up vector
+    fn basis(&self) -> Option<(Point3, Point3, Point3)> {
+        let forward = (self.target - self.eye).normalize()?;
+        let right = forward.cross(&self.up).normalize()?;
+        Some((right, right.cross(&forward), forward))
+    }
+
+    /// Get the origin and unit direction of the ray through the centre of
+    /// pixel (col, row) of an image with the given (width, height)
+    fn ray(
+        &self,
+        basis: (Point3, Point3, Point3),
+        col: u32,
+        row: u32,
+        dim: (u32, u32),
+    ) -> (Point3, Point3) {
+        let (right, up, forward) = basis;
+        let aspect = dim.0 as f32 / dim.1 as f32;
+        let u = ((col as f32 + 0.5) / dim.0 as f32 * 2.0 - 1.0) * aspect;
+        let v = 1.0 - (row as f32 + 0.5) / dim.1 as f32 * 2.0;
+
+        match self.projection {
+            CameraProjection::Orthographic { height } => {
+                let half = height * 0.5;
+                (self.eye + right * (u * half) + up * (v * half), forward)
+            }
+            CameraProjection::Perspective { fov } => {
+                let scale = (fov.to_radians() * 0.5).tan();
+                let direction = forward + right * (u * scale) + up * (v * scale);
+                (self.eye, direction.normalize().unwrap_or(forward))
+            }
+        }
+    }
+}
+
+/// Piecewise-linear mapping of intensities to opacity and colour
+///
+/// Opacity is given per mm of ray length, colours as RGB in 0..=1.
+/// Intensities outside of the control points take the value of the nearest
+/// control point. Without colour points, samples are white.
+#[derive(Clone, Default)]
+pub struct TransferFunction {
+    opacity: Vec<(f32, f32)>,
+    color: Vec<(f32, [f32; 3])>,
+}
+
+/// Get the control points around `value` and the blend factor between them
+fn locate<T>(points: &[(f32, T)], value: f32) -> Option<(&T, &T, f32)> {
+    let (first, last) = (&points.first()?.1, &points.last()?.1);
+    let index = points.partition_point(|(x, _)| *x < value);
+    if index == 0 {
+        return Some((first, first, 0.0));
+    }
+    if index == points.len() {
+        return Some((last, last, 0.0));
+    }
+    let (x0, a) = &points[index - 1];
+    let (x1, b) = &points[index];
+    Some((a, b, (value - x0) / (x1 - x0)))
+}
+
+impl TransferFunction {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Linear grey ramp, transparent below `low` and opaque above `high`
+    pub fn ramp(low: f32, high: f32) -> Self {
+        Self::new()
+            .with_opacity(low, 0.0)
+            .with_opacity(high, 1.0)
+            .with_color(low, [0.0, 0.0, 0.0])
+            .with_color(high, [1.0, 1.0, 1.0])
+    }
+
+    pub fn with_opacity(mut self, value: f32, opacity: f32) -> Self {
+        self.opacity.push((value, opacity));
+        self.opacity.sort_by(|a, b| a.0.total_cmp(&b.0));
+        self
+    }
+
+    pub fn with_color(mut self, value: f32, color: [f32; 3]) -> Self {
+        self.color.push((value, color));
+        self.color.sort_by(|a, b| a.0.total_cmp(&b.0));
+        self
+    }
+
+    /// Get the opacity per mm at an intensity
+    pub fn opacity(&self, value: f32) -> f32 {
+        locate(&self.opacity, value).map_or(0.0, |(a, b, t)| a + (b - a) * t)
+    }
+
+    /// Get the colour at an intensity
+    pub fn color(&self, value: f32) -> [f32; 3] {
+        locate(&self.color, value).map_or([1.0; 3], |(a, b, t)| {
+            std::array::from_fn(|i| a[i] + (b[i] - a[i]) * t)
+        })
+    }
+}
+
+/// Parameters of a ray-cast rendering
+pub struct VolumeRendering {
+    pub camera: Camera,
+    pub transfer_function: TransferFunction,
+    pub mode: RenderMode,
+    /// (width, height) of the image
+    pub dim: (u32, u32),
+    /// Sampling distance along the rays in mm, defaults to half of the
+    /// smallest voxel spacing
+    pub step: Option<f32>,
+}
+
+impl VolumeRendering {
+    pub fn new(camera: Camera, transfer_function: TransferFunction) -> Self {
+        Self {
+            camera,
+            transfer_function,
+            mode: RenderMode::default(),
+            dim: (512, 512),
+            step: None,
+        }
+    }
+
+    pub fn with_mode(mut self, mode: RenderMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn with_dim(mut self, dim: (u32, u32)) -> Self {
+        self.dim = dim;
+        self
+    }
+
+    pub fn with_step(mut self, step: f32) -> Self {
+        self.step = Some(step);
+        self
+    }
+}
+
+/// Get the ray parameters (near, far) within the box from 0 to `max`
+fn intersect_box(origin: Point3, direction: Point3, max: Point3) -> Option<(f32, f32)> {
+    let mut near = f32::NEG_INFINITY;
+    let mut far = f32::INFINITY;
+    for (o, d, m) in [
+        (origin.x, direction.x, max.x),
+        (origin.y, direction.y, max.y),
+        (origin.z, direction.z, max.z),
+    ] {
+        if d.abs() < f32::EPSILON {
+            if o < 0.0 || o > m {
+                return None;
+            }
+            continue;
+        }
+        let t0 = -o / d;
+        let t1 = (m - o) / d;
+        near = near.max(t0.min(t1));
+        far = far.min(t0.max(t1));
+    }
+    (near <= far && far >= 0.0).then(|| (near.max(0.0), far))
+}
+
+fn to_pixel(color: [f32; 3], alpha: f32) -> Rgba<u8> {
+    let channel = |value: f32| (value * 255.0).round().clamp(0.0, 255.0) as u8;
+    Rgba([
+        channel(color[0]),
+        channel(color[1]),
+        channel(color[2]),
+        channel(alpha),
+    ])
+}
+
+/// Ray through the voxel grid, parametrised by the distance in mm
+struct Ray<'a> {
+    source: ArrayView3<'a, f32>,
+    interpolation: &'a Interpolation,
+    origin: Point3,
+    direction: Point3,
+    spacing: (f32, f32, f32),
+}
+
+impl Ray<'_> {
+    fn position(&self, t: f32) -> Point3 {
+        self.origin + self.direction * t
+    }
+
+    fn sample_at(&self, position: Point3) -> f32 {
+        Interpolator::sample_volume(
+            &self.source,
+            position.z,
+            position.y,
+            position.x,
+            self.interpolation,
+        )
+        .unwrap_or(0.0)
+    }
+
+    fn sample(&self, t: f32) -> f32 {
+        self.sample_at(self.position(t))
+    }
+
+    /// Central difference gradient in the axis aligned mm space of the grid
+    fn gradient(&self, position: Point3) -> Point3 {
+        let difference = |offset: Point3, spacing: f32| {
+            (self.sample_at(position + offset) - self.sample_at(position - offset))
+                / (2.0 * spacing)
+        };
+        Point3::new(
+            difference(Point3::new(1.0, 0.0, 0.0), self.spacing.0),
+            difference(Point3::new(0.0, 1.0, 0.0), self.spacing.1),
+            difference(Point3::new(0.0, 0.0, 1.0), self.spacing.2),
+        )
+    }
+}
+
+impl Volume {
+    /// Render the volume by casting a ray through every pixel
+    ///
+    /// Pixels whose rays miss the volume are transparent. Returns `None` for
+    /// an empty volume or image, a non-positive step or a degenerate camera.
+    pub fn render(
+        &self,
+        rendering: &VolumeRendering,
+        interpolation: Interpolation,
+    ) -> Option<ImageBuffer<Rgba<u8>, Vec<u8>>> {
+        let (width, height) = rendering.dim;
+        if self.data.is_empty() || width == 0 || height == 0 {
+            return None;
+        }
+        let step = rendering
+            .step
+            .unwrap_or_else(|| self.spacing.0.min(self.spacing.1).min(self.spacing.2) * 0.5);
+        if step <= 0.0 {
+            return None;
+        }
+        let basis = rendering.camera.basis()?;
+
+        let source = self.samples(&interpolation);
+        let (depth, rows, cols) = self.dim();
+        let max = Point3::new((cols - 1) as f32, (rows - 1) as f32, (depth - 1) as f32);
+
+        let tiles: Vec<(u32, u32)> = (0..height)
+            .step_by(TILE_SIZE as usize)
+            .flat_map(|y| (0..width).step_by(TILE_SIZE as usize).map(move |x| (x, y)))
+            .collect();
+        let rendered: Vec<_> = tiles
+            .into_par_iter()
+            .map(|(x0, y0)| {
+                let pixels: Vec<_> = (y0..(y0 + TILE_SIZE).min(height))
+                    .flat_map(|row| (x0..(x0 + TILE_SIZE).min(width)).map(move |col| (col, row)))
+                    .map(|(col, row)| {
+                        let (origin, direction) =
+                            rendering.camera.ray(basis, col, row, rendering.dim);
+                        let voxel_origin = self.patient_to_voxel(origin);
+                        let ray = Ray {
+                            source: source.view(),
+                            interpolation: &interpolation,
+                            origin: voxel_origin,
+                            direction: self.patient_to_voxel(origin + direction) - voxel_origin,
+                            spacing: self.spacing,
+                        };
+                        let pixel = intersect_box(ray.origin, ray.direction, max)
+                            .map_or(Rgba([0; 4]), |range| {
+                                Self::cast_ray(&ray, range, step, rendering)
+                            });
+                        (col, row, pixel)
+                    })
+                    .collect();
+                pixels
+            })
+            .collect();
+
+        let mut image = ImageBuffer::new(width, height);
+        for (col, row, pixel) in rendered.into_iter().flatten() {
+            image.put_pixel(col, row, pixel);
+        }
+        Some(image)
+    }
+
+    fn cast_ray(
+        ray: &Ray<'_>,
+        (near, far): (f32, f32),
+        step: f32,
+        rendering: &VolumeRendering,
+    ) -> Rgba<u8> {
+        let transfer = &rendering.transfer_function;
+        let steps = ((far - near) / step).floor() as usize;
+        let positions = (0..=steps).map(|i| near + i as f32 * step);
+
+        match rendering.mode {
+            RenderMode::Composite => {
+                let mut color = [0.0; 3];
+                let mut alpha = 0.0;
+                for t in positions {
+                    let value = ray.sample(t);
+                    let opacity = transfer.opacity(value).clamp(0.0, 1.0);
+                    if opacity <= 0.0 {
+                        continue;
+                    }
+                    // Opacity of a segment of `step` mm
+                    let opacity = 1.0 - (1.0 - opacity).powf(step);
+                    let weight = (1.0 - alpha) * opacity;
+                    let sample_color = transfer.color(value);
+                    for (channel, sample) in color.iter_mut().zip(sample_color) {
+                        *channel += weight * sample;
+                    }
+                    alpha += weight;
+                    if alpha >= OPACITY_CUTOFF {
+                        break;
+                    }
+                }
+                if alpha > 0.0 {
+                    color = color.map(|channel| channel / alpha);
+                }
+                to_pixel(color, alpha)
+            }
+            RenderMode::Maximum => {
+                let value = positions
+                    .map(|t| ray.sample(t))
+                    .fold(f32::NEG_INFINITY, f32::max);
+                to_pixel(
+                    transfer.color(value),
+                    transfer.opacity(value).clamp(0.0, 1.0),
+                )
+            }
+            RenderMode::IsoSurface(threshold) => {
+                let mut previous: Option<(f32, f32)> = None;
+                for t in positions {
+                    let value = ray.sample(t);
+                    if value >= threshold {
+                        // Refine the crossing between the last two samples
+                        let hit = match previous {
+                            Some((t0, v0)) => t0 + (t - t0) * (threshold - v0) / (value - v0),
+                            None => t,
+                        };
+                        return Self::shade(ray, hit, transfer.color(threshold));
+                    }
+                    previous = Some((t, value));
+                }
+                Rgba([0; 4])
+            }
+        }
+    }
+
+    /// Blinn-Phong shading with the light at the eye
+    fn shade(ray: &Ray<'_>, t: f32, color: [f32; 3]) -> Rgba<u8> {
+        let view = ray.direction.scale(ray.spacing).normalize();
+        let normal = ray.gradient(ray.position(t)).normalize();
+        let (diffuse, specular) = match (normal, view) {
+            // Surfaces are lit from both sides
+            (Some(normal), Some(view)) => {
+                let cosine = normal.dot(&view).abs();
+                (cosine, cosine.powi(SHININESS))
+            }
+            _ => (1.0, 0.0),
+        };
+        let intensity = AMBIENT + DIFFUSE * diffuse;
+        to_pixel(
+            color.map(|channel| channel * intensity + SPECULAR * specular),
+            1.0,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::enums::Processor;
+    use ndarray::Array3;
+
+    /// Sphere of intensity 1000 with a radius of 6 voxels
+    fn sphere_volume() -> Volume {
+        let data = Array3::from_shape_fn((21, 21, 21), |(z, y, x)| {
+            let distance =
+                Point3::new(x as f32, y as f32, z as f32).distance(&Point3::new(10.0, 10.0, 10.0));
+            if distance <= 6.0 { 1000.0 } else { 0.0 }
+        });
+        Volume::new(data, (1.0, 1.0, 1.0))
+    }
+
+    fn top_camera(volume: &Volume) -> Camera {
+        let center = volume.center();
+        Camera::orthographic(center + Point3::new(0.0, 0.0, 50.0), center, 20.0)
+            .with_up(Point3::new(0.0, 1.0, 0.0))
+    }
+
+    #[test]
+    fn test_transfer_function_is_piecewise_linear() {
+        let transfer = TransferFunction::new()
+            .with_opacity(100.0, 0.0)
+            .with_opacity(200.0, 1.0)
+            .with_color(0.0, [1.0, 0.0, 0.0])
+            .with_color(100.0, [0.0, 0.0, 1.0]);
+
+        assert_eq!(transfer.opacity(50.0), 0.0);
+        assert_eq!(transfer.opacity(150.0), 0.5);
+        assert_eq!(transfer.opacity(300.0), 1.0);
+        assert_eq!(transfer.color(25.0), [0.75, 0.0, 0.25]);
+        assert_eq!(TransferFunction::new().color(25.0), [1.0; 3]);
+    }
+
+    #[test]
+    fn test_maximum_intensity_rendering() {
+        let volume = sphere_volume();
+        let rendering =
+            VolumeRendering::new(top_camera(&volume), TransferFunction::ramp(0.0, 1000.0))
+                .with_mode(RenderMode::Maximum)
+                .with_dim((21, 21));
+
+        let image = volume
+            .render(&rendering, Interpolation::Trilinear(Processor::CPU))
+            .unwrap();
+
+        assert_eq!(image.dimensions(), (21, 21));
+        assert_eq!(image.get_pixel(10, 10).0, [255; 4]);
+        assert_eq!(image.get_pixel(0, 0).0[3], 0);
+    }
+
+    #[test]
+    fn test_composite_rendering() {
+        let volume = sphere_volume();
+        let transfer = TransferFunction::ramp(0.0, 1000.0).with_color(0.0, [1.0, 0.0, 0.0]);
+        let rendering = VolumeRendering::new(top_camera(&volume), transfer).with_dim((21, 21));
+
+        let image = volume
+            .render(&rendering, Interpolation::Trilinear(Processor::CPU))
+            .unwrap();
+
+        let center = image.get_pixel(10, 10).0;
+        assert!(center[3] >= 250);
+        assert_eq!(center[0], 255);
+        assert_eq!(image.get_pixel(0, 0).0, [0; 4]);
+    }
+
+    #[test]
+    fn test_iso_surface_faces_the_light() {
+        let volume = sphere_volume();
+        let center = volume.center();
+        let camera = Camera::perspective(center + Point3::new(0.0, 0.0, 40.0), center, 30.0)
+            .with_up(Point3::new(0.0, 1.0, 0.0));
+        let rendering = VolumeRendering::new(camera, TransferFunction::new())
+            .with_mode(RenderMode::IsoSurface(500.0))
+            .with_dim((41, 41));
+
+        let image = volume
+            .render(&rendering, Interpolation::Trilinear(Processor::CPU))
+            .unwrap();
+
+        // The centre of the sphere faces the eye, its rim is seen edge-on
+        let front = image.get_pixel(20, 20).0;
+        let rim = (0..20)
+            .map(|col| image.get_pixel(col, 20).0)
+            .find(|pixel| pixel[3] == 255)
+            .unwrap();
+        assert_eq!(front[3], 255);
+        assert!(front[0] > rim[0]);
+        assert_eq!(image.get_pixel(0, 0).0, [0; 4]);
+    }
+}
@@ -0,0 +1,192 @@
+use crate::enums::Interpolation;
+use crate::enums::ResampleTarget;
+use crate::geometry::Point3;
+use crate::volume::Volume;
+
+/// Dimensions (depth, height, width) and spacing (x, y, z) of a grid
+type Grid = ((usize, usize, usize), (f32, f32, f32));
+
+/// Number of samples covering `len` samples at `spacing` with `new_spacing`
+fn resampled_len(len: usize, spacing: f32, new_spacing: f32) -> usize {
+    // Tolerate rounding errors so that e.g. 2.0 / 0.5 doesn't lose a sample
+    ((len - 1) as f32 * spacing / new_spacing + 1e-3).floor() as usize + 1
+}
+
+/// Spacing to cover `len` samples at `spacing` with `new_len` samples
+fn resampled_spacing(len: usize, spacing: f32, new_len: usize) -> f32 {
+    if new_len > 1 {
+        (len - 1) as f32 * spacing / (new_len - 1) as f32
+    } else {
+        spacing
+    }
+}
+
+impl Volume {
+    /// Get the grid of a resampling target
+    fn get_resample_grid(&self, target: ResampleTarget) -> Option<Grid> {
+        let (depth, height, width) = self.dim();
+        let (x_spacing, y_spacing, z_spacing) = self.spacing;
+
+        let grid = match target {
+            ResampleTarget::Isotropic => {
+                let min_spacing = x_spacing.min(y_spacing).min(z_spacing);
+                return self.get_resample_grid(ResampleTarget::Spacing((
+                    min_spacing,
+                    min_spacing,
+                    min_spacing,
+                )));
+            }
+            ResampleTarget::Spacing(spacing) => {
+                let valid = |value: f32| value.is_finite() && value > 0.0;
+                if !(valid(spacing.0) && valid(spacing.1) && valid(spacing.2)) {
+                    return None;
+                }
+                let dim = (
+                    resampled_len(depth, z_spacing, spacing.2),
+                    resampled_len(height, y_spacing, spacing.1),
+                    resampled_len(width, x_spacing, spacing.0),
+                );
+                (dim, spacing)
+            }
+            ResampleTarget::Shape(dim) => {
+                if dim.0 == 0 || dim.1 == 0 || dim.2 == 0 {
+                    return None;
+                }
+                let spacing = (
+                    resampled_spacing(width, x_spacing, dim.2),
+                    resampled_spacing(height, y_spacing, dim.1),
+                    resampled_spacing(depth, z_spacing, dim.0),
+                );
+                (dim, spacing)
+            }
+        };
+        Some(grid)
+    }
+
+    /// Get a new volume resampled to the target grid
+    ///
+    /// The first voxel keeps its position, so the resampled volume covers the
+    /// same patient space as the original one. Returns `None` for an empty
+    /// volume or an invalid target.
+    pub fn resample(&self, target: ResampleTarget, interpolation: Interpolation) -> Option<Volume> {
+        if self.data.is_empty() {
+            return None;
+        }
+
+        let (dim, spacing) = self.get_resample_grid(target)?;
+        let step = Point3::new(
+            spacing.0 / self.spacing.0,
+            spacing.1 / self.spacing.1,
+            spacing.2 / self.spacing.2,
+        );
+
+        let backend = interpolation.processor().backend();
+        let data =
+            backend.resample_volume(&self.samples(&interpolation), dim, step, &interpolation);
+
+        Some(Volume::new(data, spacing).with_geometry(self.origin, self.direction))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::enums::Processor;
+    use ndarray::Array3;
+
+    fn test_volume() -> Volume {
+        // Intensity equals the slice position in mm
+        let data = Array3::from_shape_fn((3, 4, 4), |(z, _, _)| z as f32 * 2.0);
+        Volume::new(data, (1.0, 1.0, 2.0))
+    }
+
+    #[test]
+    fn test_resample_isotropic() {
+        let volume = test_volume().with_geometry(
+            Point3::new(-10.0, 5.0, 100.0),
+            crate::geometry::IDENTITY_DIRECTION,
+        );
+
+        let result = volume
+            .resample(
+                ResampleTarget::Isotropic,
+                Interpolation::Trilinear(Processor::CPU),
+            )
+            .unwrap();
+
+        assert_eq!(result.dim(), (5, 4, 4));
+        assert_eq!(result.spacing, (1.0, 1.0, 1.0));
+        assert_eq!(result.origin, volume.origin);
+        for ((z, _, _), &value) in result.data.indexed_iter() {
+            assert!((value - z as f32).abs() < 1e-5);
+        }
+        assert_eq!(
+            result.voxel_to_patient(Point3::new(3.0, 3.0, 4.0)),
+            volume.voxel_to_patient(Point3::new(3.0, 3.0, 2.0))
+        );
+    }
+
+    #[test]
+    fn test_resample_to_spacing() {
+        let volume = test_volume();
+
+        let result = volume
+            .resample(
+                ResampleTarget::Spacing((0.5, 1.5, 4.0)),
+                Interpolation::Trilinear(Processor::CPU),
+            )
+            .unwrap();
+
+        assert_eq!(result.dim(), (2, 3, 7));
+        assert_eq!(result.spacing, (0.5, 1.5, 4.0));
+        assert_eq!(result.data[[1, 0, 0]], 4.0);
+    }
+
+    #[test]
+    fn test_resample_to_shape() {
+        let volume = test_volume();
+
+        let result = volume
+            .resample(
+                ResampleTarget::Shape((9, 4, 4)),
+                Interpolation::Trilinear(Processor::CPU),
+            )
+            .unwrap();
+
+        assert_eq!(result.dim(), (9, 4, 4));
+        assert_eq!(result.spacing, (1.0, 1.0, 0.5));
+        assert!((result.data[[3, 1, 1]] - 1.5).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_resample_invalid_target() {
+        let volume = test_volume();
+
+        assert!(
+            volume
+                .resample(
+                    ResampleTarget::Spacing((0.0, 1.0, 1.0)),
+                    Interpolation::None
+                )
+                .is_none()
+        );
+        assert!(
+            volume
+                .resample(ResampleTarget::Shape((0, 1, 1)), Interpolation::None)
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_resample_rejects_non_finite_spacing() {
+        let volume = test_volume();
+
+        for spacing in [(f32::NAN, 1.0, 1.0), (1.0, 1.0, f32::INFINITY)] {
+            assert!(
+                volume
+                    .resample(ResampleTarget::Spacing(spacing), Interpolation::None)
+                    .is_none()
+            );
+        }
+    }
+}
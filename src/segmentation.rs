@@ -0,0 +1,261 @@
+use crate::enums::Connectivity;
+use crate::geometry::Point3;
+use crate::volume::Volume;
+
+use ndarray::Array3;
+use ndarray::Zip;
+
+/// A connected component of a mask, see [`Volume::label_components`]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Component {
+    /// Value of the voxels of this component in [`Components::labels`]
+    pub label: u32,
+    /// Number of voxels
+    pub size: usize,
+    /// Volume covered by the voxels in mL
+    pub volume_ml: f32,
+    /// Corners of the bounding box in voxel coordinates, both inclusive
+    pub min: Point3,
+    pub max: Point3,
+    /// Mean voxel coordinates
+    pub centroid: Point3,
+}
+
+/// Connected components of a mask
+#[derive(Clone, Debug)]
+pub struct Components {
+    /// Labels aligned with the mask, 0 is background and component `i` has
+    /// the label `i + 1`
+    pub labels: Array3<u32>,
+    /// Components ordered by their first voxel in (z, y, x) order
+    pub components: Vec<Component>,
+    /// Spacing and geometry of the labelled volume
+    pub spacing: (f32, f32, f32),
+    pub origin: Point3,
+    pub direction: [Point3; 3],
+}
+
+/// Get the neighbour of a voxel at an offset, `None` outside of the volume
+#[inline]
+pub(crate) fn neighbour(
+    index: [usize; 3],
+    offset: [isize; 3],
+    shape: &[usize],
+) -> Option<[usize; 3]> {
+    let mut neighbour = [0; 3];
+    for axis in 0..3 {
+        let position = index[axis] as isize + offset[axis];
+        if !(0..shape[axis] as isize).contains(&position) {
+            return None;
+        }
+        neighbour[axis] = position as usize;
+    }
+    Some(neighbour)
+}
+
+impl Components {
+    /// Get the component with the most voxels, the first one on ties
+    pub fn largest(&self) -> Option<&Component> {
+        self.components.iter().reduce(|largest, component| {
+            if component.size > largest.size {
+                component
+            } else {
+                largest
+            }
+        })
+    }
+
+    /// Get a mask of the components for which `keep` returns true
+    pub fn mask<F>(&self, keep: F) -> Volume
+    where
+        F: Fn(&Component) -> bool,
+    {
+        let kept: Vec<bool> = std::iter::once(false)
+            .chain(self.components.iter().map(keep))
+            .collect();
+        let data = Zip::from(&self.labels)
+            .par_map_collect(|&label| if kept[label as usize] { 1.0 } else { 0.0 });
+        Volume::new(data, self.spacing).with_geometry(self.origin, self.direction)
+    }
+
+    /// Get the labels as volume
+    ///
+    /// Labels above 2^24 are not exactly representable as intensities.
+    pub fn label_volume(&self) -> Volume {
+        let data = self.labels.mapv(|label| label as f32);
+        Volume::new(data, self.spacing).with_geometry(self.origin, self.direction)
+    }
+
+    /// Get a mask of the largest component only
+    pub fn keep_largest(&self) -> Volume {
+        let largest = self.largest().map(|component| component.label);
+        self.mask(|component| Some(component.label) == largest)
+    }
+
+    /// Get a mask of the components with at least `size` voxels
+    pub fn remove_smaller_than(&self, size: usize) -> Volume {
+        self.mask(|component| component.size >= size)
+    }
+}
+
+impl Volume {
+    /// Get a mask of the voxels with an intensity in `lower..=upper`
+    ///
+    /// Voxels inside the range are 1, all others 0. Use infinite bounds for
+    /// open ranges, e.g. `threshold(300.0, f32::INFINITY)` for bone in HU.
+    pub fn threshold(&self, lower: f32, upper: f32) -> Volume {
+        let data = Zip::from(&self.data).par_map_collect(|&value| {
+            if (lower..=upper).contains(&value) {
+                1.0
+            } else {
+                0.0
+            }
+        });
+        Volume::new(data, self.spacing).with_geometry(self.origin, self.direction)
+    }
+
+    /// Label the connected components of the non-zero voxels
+    pub fn label_components(&self, connectivity: Connectivity) -> Components {
+        let shape = self.data.shape();
+        let offsets = connectivity.offsets();
+        let mut labels = Array3::<u32>::zeros(self.data.raw_dim());
+        let mut components = Vec::new();
+        let mut stack = Vec::new();
+
+        for ((z, y, x), &value) in self.data.indexed_iter() {
+            if value == 0.0 || labels[[z, y, x]] != 0 {
+                continue;
+            }
+            let label = components.len() as u32 + 1;
+            labels[[z, y, x]] = label;
+            stack.push([z, y, x]);
+
+            let mut size = 0usize;
+            let mut sum = [0f64; 3];
+            let mut min = [z, y, x];
+            let mut max = [z, y, x];
+            while let Some(index) = stack.pop() {
+                size += 1;
+                for axis in 0..3 {
+                    sum[axis] += index[axis] as f64;
+                    min[axis] = min[axis].min(index[axis]);
+                    max[axis] = max[axis].max(index[axis]);
+                }
+                for &offset in &offsets {
+                    if let Some(next) = neighbour(index, offset, shape)
+                        && self.data[next] != 0.0
+                        && labels[next] == 0
+                    {
+                        labels[next] = label;
+                        stack.push(next);
+                    }
+                }
+            }
+
+            let point =
+                |index: [usize; 3]| Point3::new(index[2] as f32, index[1] as f32, index[0] as f32);
+            let mean = |axis: usize| (sum[axis] / size as f64) as f32;
+            components.push(Component {
+                label,
+                size,
+                volume_ml: size as f32 * self.voxel_volume_ml(),
+                min: point(min),
+                max: point(max),
+                centroid: Point3::new(mean(2), mean(1), mean(0)),
+            });
+        }
+
+        Components {
+            labels,
+            components,
+            spacing: self.spacing,
+            origin: self.origin,
+            direction: self.direction,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::s;
+
+    /// Two cubes of 27 and 8 voxels and a voxel touching the large cube
+    /// diagonally, in 1 × 1 × 2 mm voxels
+    fn test_volume() -> Volume {
+        let mut data = Array3::from_elem((8, 8, 8), -100.0);
+        data.slice_mut(s![1..4, 1..4, 1..4]).fill(500.0);
+        data.slice_mut(s![5..7, 5..7, 1..3]).fill(400.0);
+        data[[4, 4, 4]] = 350.0;
+        Volume::new(data, (1.0, 1.0, 2.0))
+    }
+
+    #[test]
+    fn test_threshold() {
+        let volume = test_volume();
+
+        let bone = volume.threshold(300.0, f32::INFINITY);
+        let dense = volume.threshold(450.0, 600.0);
+
+        assert_eq!(bone.data.sum(), 36.0);
+        assert_eq!(dense.data.sum(), 27.0);
+        assert_eq!(bone.data[[4, 4, 4]], 1.0);
+        assert_eq!(bone.spacing, volume.spacing);
+    }
+
+    #[test]
+    fn test_connectivity() {
+        let mask = test_volume().threshold(300.0, f32::INFINITY);
+
+        let face = mask.label_components(Connectivity::Face);
+        let vertex = mask.label_components(Connectivity::Vertex);
+
+        assert_eq!(Connectivity::Face.offsets().len(), 6);
+        assert_eq!(Connectivity::Edge.offsets().len(), 18);
+        assert_eq!(Connectivity::Vertex.offsets().len(), 26);
+        let sizes = |components: &Components| -> Vec<usize> {
+            components.components.iter().map(|c| c.size).collect()
+        };
+        assert_eq!(sizes(&face), vec![27, 1, 8]);
+        assert_eq!(sizes(&vertex), vec![28, 8]);
+        assert_eq!(face.labels[[4, 4, 4]], 2);
+        assert_eq!(face.label_volume().data[[4, 4, 4]], 2.0);
+        assert_eq!(face.label_volume().spacing, mask.spacing);
+    }
+
+    #[test]
+    fn test_component_geometry() {
+        let mask = test_volume().threshold(300.0, f32::INFINITY);
+
+        let components = mask.label_components(Connectivity::Face);
+        let small = &components.components[2];
+
+        assert_eq!(small.label, 3);
+        assert_eq!(small.min, Point3::new(1.0, 5.0, 5.0));
+        assert_eq!(small.max, Point3::new(2.0, 6.0, 6.0));
+        assert_eq!(small.centroid, Point3::new(1.5, 5.5, 5.5));
+        assert!((small.volume_ml - 0.016).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_keep_largest_and_remove_small() {
+        let mask = test_volume().threshold(300.0, f32::INFINITY);
+        let components = mask.label_components(Connectivity::Face);
+
+        let largest = components.keep_largest();
+        let cleaned = components.remove_smaller_than(2);
+
+        assert_eq!(largest.data.sum(), 27.0);
+        assert_eq!(largest.data[[2, 2, 2]], 1.0);
+        assert_eq!(cleaned.data.sum(), 35.0);
+        assert_eq!(cleaned.data[[4, 4, 4]], 0.0);
+        assert_eq!(
+            Volume::default()
+                .label_components(Connectivity::Face)
+                .keep_largest()
+                .data
+                .len(),
+            0
+        );
+    }
+}
@@ -0,0 +1,115 @@
+// Compute shaders of the GPU backend. The volume is stored in row-major
+// (depth, height, width) order, positions are voxel coordinates.
+
+struct Params {
+    origin: vec4<f32>,
+    row_step: vec4<f32>,
+    col_step: vec4<f32>,
+    // depth, height, width of the volume
+    volume_dim: vec4<u32>,
+    // output height, output width, interpolation or axis, projection mode
+    options: vec4<u32>,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read> volume: array<f32>;
+@group(0) @binding(2) var<storage, read_write> output: array<f32>;
+
+const BOUNDS_TOLERANCE: f32 = 1e-3;
+
+const NEAREST: u32 = 0u;
+
+const MAXIMUM: u32 = 0u;
+const MINIMUM: u32 = 1u;
+const MEAN: u32 = 2u;
+
+fn voxel(z: u32, y: u32, x: u32) -> f32 {
+    return volume[(z * params.volume_dim.y + y) * params.volume_dim.z + x];
+}
+
+@compute @workgroup_size(8, 8)
+fn resample(@builtin(global_invocation_id) id: vec3<u32>) {
+    let row = id.y;
+    let col = id.x;
+    if row >= params.options.x || col >= params.options.y {
+        return;
+    }
+
+    let position = params.origin.xyz + params.row_step.xyz * f32(row) + params.col_step.xyz * f32(col);
+    // (x, y, z) order like the positions
+    let last = params.volume_dim.zyx - vec3<u32>(1u);
+    let max_position = vec3<f32>(last);
+
+    var value = 0.0;
+    if all(position >= vec3<f32>(-BOUNDS_TOLERANCE)) && all(position <= max_position + BOUNDS_TOLERANCE) {
+        let p = clamp(position, vec3<f32>(0.0), max_position);
+        let nearest = vec3<u32>(floor(p + 0.5));
+
+        switch params.options.z {
+            case NEAREST: {
+                value = voxel(nearest.z, nearest.y, nearest.x);
+            }
+            default: {
+                let f = floor(p);
+                let i0 = vec3<u32>(f);
+                let i1 = min(i0 + vec3<u32>(1u), last);
+                let d = p - f;
+                let v00 = mix(voxel(i0.z, i0.y, i0.x), voxel(i0.z, i0.y, i1.x), d.x);
+                let v01 = mix(voxel(i0.z, i1.y, i0.x), voxel(i0.z, i1.y, i1.x), d.x);
+                let v10 = mix(voxel(i1.z, i0.y, i0.x), voxel(i1.z, i0.y, i1.x), d.x);
+                let v11 = mix(voxel(i1.z, i1.y, i0.x), voxel(i1.z, i1.y, i1.x), d.x);
+                value = mix(mix(v00, v01, d.y), mix(v10, v11, d.y), d.z);
+            }
+        }
+    }
+
+    output[row * params.options.y + col] = value;
+}
+
+fn lane_voxel(axis: u32, row: u32, col: u32, i: u32) -> f32 {
+    switch axis {
+        case 0u: {
+            return voxel(i, row, col);
+        }
+        case 1u: {
+            return voxel(row, i, col);
+        }
+        default: {
+            return voxel(row, col, i);
+        }
+    }
+}
+
+@compute @workgroup_size(8, 8)
+fn project(@builtin(global_invocation_id) id: vec3<u32>) {
+    let row = id.y;
+    let col = id.x;
+    if row >= params.options.x || col >= params.options.y {
+        return;
+    }
+
+    let axis = params.options.z;
+    let mode = params.options.w;
+    let len = params.volume_dim[axis];
+
+    var value = lane_voxel(axis, row, col, 0u);
+    for (var i = 1u; i < len; i++) {
+        let v = lane_voxel(axis, row, col, i);
+        switch mode {
+            case MAXIMUM: {
+                value = max(value, v);
+            }
+            case MINIMUM: {
+                value = min(value, v);
+            }
+            default: {
+                value += v;
+            }
+        }
+    }
+    if mode == MEAN {
+        value /= f32(len);
+    }
+
+    output[row * params.options.y + col] = value;
+}
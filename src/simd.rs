@@ -0,0 +1,530 @@
+//! Vectorised CPU backend.
+//!
+//! Bilinear and trilinear resampling and the 8-bit conversion use AVX2 and
+//! FMA if the running CPU supports them. The operations are evaluated in the
+//! same order as in [`CpuBackend`], so both backends produce identical
+//! results. Everything else, and CPUs without these features, fall back to
+//! [`CpuBackend`].
+
+use crate::backend::ComputeBackend;
+use crate::backend::CpuBackend;
+use crate::backend::PlaneGrid;
+use crate::backend::plane_sampling;
+use crate::enums::Interpolation;
+use crate::geometry::Point3;
+
+use ndarray::Array2;
+use ndarray::Array3;
+use ndarray::ArrayView2;
+use ndarray::ArrayView3;
+
+/// Vectorised CPU backend using rayon and AVX2/FMA
+pub struct SimdBackend;
+
+impl SimdBackend {
+    /// Whether the running CPU supports the vectorised code paths
+    pub fn is_supported() -> bool {
+        #[cfg(target_arch = "x86_64")]
+        {
+            is_x86_feature_detected!("avx2") && is_x86_feature_detected!("fma")
+        }
+        #[cfg(not(target_arch = "x86_64"))]
+        {
+            false
+        }
+    }
+}
+
+impl ComputeBackend for SimdBackend {
+    fn name(&self) -> &str {
+        "simd"
+    }
+
+    fn resample_slice(
+        &self,
+        slice: &ArrayView2<'_, f32>,
+        dim: (usize, usize),
+        interpolation: &Interpolation,
+    ) -> Array2<f32> {
+        #[cfg(target_arch = "x86_64")]
+        if Self::is_supported()
+            && matches!(
+                interpolation,
+                Interpolation::Bilinear(_) | Interpolation::Trilinear(_)
+            )
+            && slice.len() <= i32::MAX as usize
+        {
+            return x86::resample_slice_bilinear(slice, dim);
+        }
+        CpuBackend.resample_slice(slice, dim, interpolation)
+    }
+
+    fn resample_plane(
+        &self,
+        volume: &ArrayView3<'_, f32>,
+        grid: &PlaneGrid,
+        interpolation: &Interpolation,
+    ) -> Array2<f32> {
+        let (grid, interpolation) = plane_sampling(grid, interpolation);
+        #[cfg(target_arch = "x86_64")]
+        if Self::is_supported()
+            && matches!(interpolation, Interpolation::Trilinear(_))
+            && volume.len() <= i32::MAX as usize
+        {
+            return x86::resample_plane_trilinear(volume, &grid);
+        }
+        CpuBackend.resample_plane(volume, &grid, &interpolation)
+    }
+
+    fn resample_volume(
+        &self,
+        volume: &ArrayView3<'_, f32>,
+        dim: (usize, usize, usize),
+        step: Point3,
+        interpolation: &Interpolation,
+    ) -> Array3<f32> {
+        #[cfg(target_arch = "x86_64")]
+        if Self::is_supported()
+            && matches!(
+                interpolation,
+                Interpolation::Bilinear(_) | Interpolation::Trilinear(_)
+            )
+            && volume.len() <= i32::MAX as usize
+        {
+            return x86::resample_volume_trilinear(volume, dim, step);
+        }
+        CpuBackend.resample_volume(volume, dim, step, interpolation)
+    }
+
+    fn to_u8(&self, values: &ArrayView2<'_, f32>) -> Vec<u8> {
+        #[cfg(target_arch = "x86_64")]
+        if Self::is_supported() {
+            return x86::to_u8(values);
+        }
+        CpuBackend.to_u8(values)
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+mod x86 {
+    use crate::backend::PlaneGrid;
+    use crate::geometry::Point3;
+    use crate::interpolator::BOUNDS_TOLERANCE;
+    use crate::interpolator::Interpolator;
+    use crate::volume::Volume;
+
+    use ndarray::Array2;
+    use ndarray::Array3;
+    use ndarray::ArrayView2;
+    use ndarray::ArrayView3;
+    use ndarray::Axis;
+    use rayon::iter::IndexedParallelIterator;
+    use rayon::iter::IntoParallelIterator;
+    use rayon::iter::ParallelIterator;
+    use rayon::slice::ParallelSlice;
+    use rayon::slice::ParallelSliceMut;
+    use std::arch::x86_64::*;
+
+    const LANES: usize = 8;
+
+    pub(super) fn resample_slice_bilinear(
+        slice: &ArrayView2<'_, f32>,
+        dim: (usize, usize),
+    ) -> Array2<f32> {
+        let source = slice.as_standard_layout();
+        let (height, width) = source.dim();
+        let mut result = Array2::<f32>::zeros(dim);
+        if height == 0 || width == 0 || result.is_empty() {
+            return result;
+        }
+        let data = source.as_slice().expect("standard layout");
+
+        let max_y = (height - 1) as f32;
+        let max_x = (width - 1) as f32;
+        let scale_y = max_y / (dim.0.max(2) - 1) as f32;
+        let scale_x = max_x / (dim.1.max(2) - 1) as f32;
+
+        // The columns sample the same x positions in every row
+        let mut x0 = Vec::with_capacity(dim.1);
+        let mut x1 = Vec::with_capacity(dim.1);
+        let mut dx = Vec::with_capacity(dim.1);
+        for col in 0..dim.1 {
+            let x = (col as f32 * scale_x).min(max_x);
+            let floor = x.floor() as usize;
+            x0.push(floor as i32);
+            x1.push((floor + 1).min(width - 1) as i32);
+            dx.push(x - floor as f32);
+        }
+
+        result
+            .as_slice_mut()
+            .expect("standard layout")
+            .par_chunks_mut(dim.1)
+            .enumerate()
+            .for_each(|(row, out)| {
+                let y = (row as f32 * scale_y).min(max_y);
+                let y0 = y.floor() as usize;
+                let y1 = (y0 + 1).min(height - 1);
+                let dy = y - y0 as f32;
+                let row0 = &data[y0 * width..(y0 + 1) * width];
+                let row1 = &data[y1 * width..(y1 + 1) * width];
+                // SAFETY: the caller checked for AVX2 and FMA support
+                unsafe { bilinear_row(row0, row1, &x0, &x1, &dx, dy, out) };
+            });
+
+        result
+    }
+
+    #[target_feature(enable = "avx2,fma")]
+    fn bilinear_row(
+        row0: &[f32],
+        row1: &[f32],
+        x0: &[i32],
+        x1: &[i32],
+        dx: &[f32],
+        dy: f32,
+        out: &mut [f32],
+    ) {
+        let one_minus_dy = 1.0 - dy;
+        let one = _mm256_set1_ps(1.0);
+        let vdy = _mm256_set1_ps(dy);
+        let vomdy = _mm256_set1_ps(one_minus_dy);
+
+        let vectorised = out.len() / LANES * LANES;
+        for i in (0..vectorised).step_by(LANES) {
+            // SAFETY: i + LANES <= out.len() == x0.len() == x1.len() == dx.len()
+            // and all column indices are smaller than the row length
+            unsafe {
+                let ix0 = _mm256_loadu_si256(x0.as_ptr().add(i).cast());
+                let ix1 = _mm256_loadu_si256(x1.as_ptr().add(i).cast());
+                let vdx = _mm256_loadu_ps(dx.as_ptr().add(i));
+                let vomdx = _mm256_sub_ps(one, vdx);
+
+                let v00 = _mm256_i32gather_ps::<4>(row0.as_ptr(), ix0);
+                let v01 = _mm256_i32gather_ps::<4>(row0.as_ptr(), ix1);
+                let v10 = _mm256_i32gather_ps::<4>(row1.as_ptr(), ix0);
+                let v11 = _mm256_i32gather_ps::<4>(row1.as_ptr(), ix1);
+
+                let v0 = _mm256_fmadd_ps(v00, vomdx, _mm256_mul_ps(v01, vdx));
+                let v1 = _mm256_fmadd_ps(v10, vomdx, _mm256_mul_ps(v11, vdx));
+                let value = _mm256_fmadd_ps(v0, vomdy, _mm256_mul_ps(v1, vdy));
+                _mm256_storeu_ps(out.as_mut_ptr().add(i), value);
+            }
+        }
+
+        for i in vectorised..out.len() {
+            let (x0, x1) = (x0[i] as usize, x1[i] as usize);
+            let one_minus_dx = 1.0 - dx[i];
+            let v0 = row0[x0].mul_add(one_minus_dx, row0[x1] * dx[i]);
+            let v1 = row1[x0].mul_add(one_minus_dx, row1[x1] * dx[i]);
+            out[i] = v0.mul_add(one_minus_dy, v1 * dy);
+        }
+    }
+
+    pub(super) fn resample_plane_trilinear(
+        volume: &ArrayView3<'_, f32>,
+        grid: &PlaneGrid,
+    ) -> Array2<f32> {
+        let source = volume.as_standard_layout();
+        let mut result = Array2::<f32>::zeros(grid.dim);
+        if source.is_empty() || result.is_empty() {
+            return result;
+        }
+        let data = source.as_slice().expect("standard layout");
+        let dim = source.dim();
+
+        result
+            .as_slice_mut()
+            .expect("standard layout")
+            .par_chunks_mut(grid.dim.1)
+            .enumerate()
+            .for_each(|(row, out)| {
+                let start = grid.origin + grid.row_step * row as f32;
+                // SAFETY: the caller checked for AVX2 and FMA support
+                unsafe { trilinear_row(data, dim, start, grid.col_step, out) };
+            });
+
+        result
+    }
+
+    pub(super) fn resample_volume_trilinear(
+        volume: &ArrayView3<'_, f32>,
+        dim: (usize, usize, usize),
+        step: Point3,
+    ) -> Array3<f32> {
+        let source = volume.as_standard_layout();
+        let mut result = Array3::<f32>::zeros(dim);
+        if source.is_empty() || result.is_empty() {
+            return result;
+        }
+        let data = source.as_slice().expect("standard layout");
+        let source_dim = source.dim();
+
+        result
+            .axis_iter_mut(Axis(0))
+            .into_par_iter()
+            .enumerate()
+            .for_each(|(k, mut plane)| {
+                let out = plane.as_slice_mut().expect("standard layout");
+                for (i, out_row) in out.chunks_mut(dim.2).enumerate() {
+                    let start = Point3::new(0.0, i as f32 * step.y, k as f32 * step.z);
+                    let col_step = Point3::new(step.x, 0.0, 0.0);
+                    // SAFETY: the caller checked for AVX2 and FMA support
+                    unsafe { trilinear_row(data, source_dim, start, col_step, out_row) };
+                }
+            });
+
+        result
+    }
+
+    /// Sample one output row at `start + col_step * col` with trilinear interpolation
+    #[target_feature(enable = "avx2,fma")]
+    fn trilinear_row(
+        data: &[f32],
+        dim: (usize, usize, usize),
+        start: Point3,
+        col_step: Point3,
+        out: &mut [f32],
+    ) {
+        let (depth, height, width) = dim;
+        let max_z = depth as f32 - 1.0;
+        let max_y = height as f32 - 1.0;
+        let max_x = width as f32 - 1.0;
+
+        let zero = _mm256_setzero_ps();
+        let one = _mm256_set1_ps(1.0);
+        let one_i = _mm256_set1_epi32(1);
+        let lanes = _mm256_setr_ps(0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0);
+        let low = _mm256_set1_ps(-BOUNDS_TOLERANCE);
+        let vmax_z = _mm256_set1_ps(max_z);
+        let vmax_y = _mm256_set1_ps(max_y);
+        let vmax_x = _mm256_set1_ps(max_x);
+        let high_z = _mm256_set1_ps(max_z + BOUNDS_TOLERANCE);
+        let high_y = _mm256_set1_ps(max_y + BOUNDS_TOLERANCE);
+        let high_x = _mm256_set1_ps(max_x + BOUNDS_TOLERANCE);
+        let last_z = _mm256_set1_epi32(depth as i32 - 1);
+        let last_y = _mm256_set1_epi32(height as i32 - 1);
+        let last_x = _mm256_set1_epi32(width as i32 - 1);
+        let vheight = _mm256_set1_epi32(height as i32);
+        let vwidth = _mm256_set1_epi32(width as i32);
+
+        let vectorised = out.len() / LANES * LANES;
+        for i in (0..vectorised).step_by(LANES) {
+            let cols = _mm256_add_ps(_mm256_set1_ps(i as f32), lanes);
+            let z = _mm256_add_ps(
+                _mm256_set1_ps(start.z),
+                _mm256_mul_ps(_mm256_set1_ps(col_step.z), cols),
+            );
+            let y = _mm256_add_ps(
+                _mm256_set1_ps(start.y),
+                _mm256_mul_ps(_mm256_set1_ps(col_step.y), cols),
+            );
+            let x = _mm256_add_ps(
+                _mm256_set1_ps(start.x),
+                _mm256_mul_ps(_mm256_set1_ps(col_step.x), cols),
+            );
+
+            let inside = _mm256_and_ps(
+                _mm256_and_ps(
+                    _mm256_and_ps(
+                        _mm256_cmp_ps::<_CMP_GE_OQ>(z, low),
+                        _mm256_cmp_ps::<_CMP_LE_OQ>(z, high_z),
+                    ),
+                    _mm256_and_ps(
+                        _mm256_cmp_ps::<_CMP_GE_OQ>(y, low),
+                        _mm256_cmp_ps::<_CMP_LE_OQ>(y, high_y),
+                    ),
+                ),
+                _mm256_and_ps(
+                    _mm256_cmp_ps::<_CMP_GE_OQ>(x, low),
+                    _mm256_cmp_ps::<_CMP_LE_OQ>(x, high_x),
+                ),
+            );
+
+            let z = _mm256_min_ps(_mm256_max_ps(z, zero), vmax_z);
+            let y = _mm256_min_ps(_mm256_max_ps(y, zero), vmax_y);
+            let x = _mm256_min_ps(_mm256_max_ps(x, zero), vmax_x);
+
+            let fz = _mm256_floor_ps(z);
+            let fy = _mm256_floor_ps(y);
+            let fx = _mm256_floor_ps(x);
+            let z0 = _mm256_cvttps_epi32(fz);
+            let y0 = _mm256_cvttps_epi32(fy);
+            let x0 = _mm256_cvttps_epi32(fx);
+            let z1 = _mm256_min_epi32(_mm256_add_epi32(z0, one_i), last_z);
+            let y1 = _mm256_min_epi32(_mm256_add_epi32(y0, one_i), last_y);
+            let x1 = _mm256_min_epi32(_mm256_add_epi32(x0, one_i), last_x);
+
+            let dz = _mm256_sub_ps(z, fz);
+            let dy = _mm256_sub_ps(y, fy);
+            let dx = _mm256_sub_ps(x, fx);
+            let omdz = _mm256_sub_ps(one, dz);
+            let omdy = _mm256_sub_ps(one, dy);
+            let omdx = _mm256_sub_ps(one, dx);
+
+            let row = |z: __m256i, y: __m256i| {
+                _mm256_mullo_epi32(_mm256_add_epi32(_mm256_mullo_epi32(z, vheight), y), vwidth)
+            };
+            let r00 = row(z0, y0);
+            let r01 = row(z0, y1);
+            let r10 = row(z1, y0);
+            let r11 = row(z1, y1);
+
+            // SAFETY: all indices are clamped to the volume, which has at
+            // most i32::MAX elements, and i + LANES <= out.len()
+            unsafe {
+                let gather = |index: __m256i| _mm256_i32gather_ps::<4>(data.as_ptr(), index);
+                let v000 = gather(_mm256_add_epi32(r00, x0));
+                let v001 = gather(_mm256_add_epi32(r00, x1));
+                let v010 = gather(_mm256_add_epi32(r01, x0));
+                let v011 = gather(_mm256_add_epi32(r01, x1));
+                let v100 = gather(_mm256_add_epi32(r10, x0));
+                let v101 = gather(_mm256_add_epi32(r10, x1));
+                let v110 = gather(_mm256_add_epi32(r11, x0));
+                let v111 = gather(_mm256_add_epi32(r11, x1));
+
+                let v00 = _mm256_fmadd_ps(v000, omdx, _mm256_mul_ps(v001, dx));
+                let v01 = _mm256_fmadd_ps(v010, omdx, _mm256_mul_ps(v011, dx));
+                let v10 = _mm256_fmadd_ps(v100, omdx, _mm256_mul_ps(v101, dx));
+                let v11 = _mm256_fmadd_ps(v110, omdx, _mm256_mul_ps(v111, dx));
+
+                let v0 = _mm256_fmadd_ps(v00, omdy, _mm256_mul_ps(v01, dy));
+                let v1 = _mm256_fmadd_ps(v10, omdy, _mm256_mul_ps(v11, dy));
+                let value = _mm256_fmadd_ps(v0, omdz, _mm256_mul_ps(v1, dz));
+
+                _mm256_storeu_ps(out.as_mut_ptr().add(i), _mm256_and_ps(value, inside));
+            }
+        }
+
+        let volume = ArrayView3::from_shape(dim, data).expect("matching shape");
+        for (col, value) in out.iter_mut().enumerate().skip(vectorised) {
+            let position = start + col_step * col as f32;
+            *value = Interpolator::sample_volume(
+                &volume,
+                position.z,
+                position.y,
+                position.x,
+                &crate::enums::Interpolation::Trilinear(crate::enums::Processor::CPU),
+            )
+            .unwrap_or(0.0);
+        }
+    }
+
+    pub(super) fn to_u8(values: &ArrayView2<'_, f32>) -> Vec<u8> {
+        let source = values.as_standard_layout();
+        let data = source.as_slice().expect("standard layout");
+        let mut pixels = vec![0; data.len()];
+
+        const CHUNK: usize = 4096;
+        pixels
+            .par_chunks_mut(CHUNK)
+            .zip(data.par_chunks(CHUNK))
+            .for_each(|(out, input)| {
+                // SAFETY: the caller checked for AVX2 support
+                unsafe { to_u8_chunk(input, out) };
+            });
+
+        pixels
+    }
+
+    #[target_feature(enable = "avx2,fma")]
+    fn to_u8_chunk(input: &[f32], out: &mut [u8]) {
+        const BLOCK: usize = 4 * LANES;
+
+        let zero = _mm256_setzero_ps();
+        let max = _mm256_set1_ps(255.0);
+        let range = _mm256_set1_ps(65535.0);
+        // Undo the lane interleaving of the pack instructions
+        let order = _mm256_setr_epi32(0, 4, 1, 5, 2, 6, 3, 7);
+
+        let convert = |offset: usize| {
+            // SAFETY: offset + LANES <= input.len()
+            let v = unsafe { _mm256_loadu_ps(input.as_ptr().add(offset)) };
+            let v = _mm256_mul_ps(_mm256_div_ps(v, range), max);
+            _mm256_cvttps_epi32(_mm256_min_ps(_mm256_max_ps(v, zero), max))
+        };
+
+        let vectorised = input.len() / BLOCK * BLOCK;
+        for i in (0..vectorised).step_by(BLOCK) {
+            let ab = _mm256_packus_epi32(convert(i), convert(i + LANES));
+            let cd = _mm256_packus_epi32(convert(i + 2 * LANES), convert(i + 3 * LANES));
+            let bytes = _mm256_permutevar8x32_epi32(_mm256_packus_epi16(ab, cd), order);
+            // SAFETY: i + BLOCK <= out.len()
+            unsafe { _mm256_storeu_si256(out.as_mut_ptr().add(i).cast(), bytes) };
+        }
+
+        for i in vectorised..input.len() {
+            out[i] = Volume::normalize_to_u8(input[i]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::ScalarBackend;
+    use crate::enums::Processor;
+    use ndarray::Axis;
+
+    fn test_data() -> Array3<f32> {
+        Array3::from_shape_fn((9, 13, 21), |(z, y, x)| {
+            ((z * 131 + y * 71 + x * 37) % 97) as f32 * 700.0 - 1000.0
+        })
+    }
+
+    #[test]
+    fn test_bilinear_slice_matches_scalar() {
+        let data = test_data();
+        let interpolation = Interpolation::Bilinear(Processor::SIMD);
+
+        for axis in 0..3 {
+            let slice = data.index_axis(Axis(axis), 4);
+            assert_eq!(
+                SimdBackend.resample_slice(&slice, (29, 35), &interpolation),
+                ScalarBackend.resample_slice(&slice, (29, 35), &interpolation)
+            );
+        }
+    }
+
+    #[test]
+    fn test_trilinear_plane_matches_scalar() {
+        let data = test_data();
+        let interpolation = Interpolation::Trilinear(Processor::SIMD);
+        // Oblique plane that partially leaves the volume
+        let grid = PlaneGrid {
+            origin: Point3::new(-2.0, 0.5, 1.25),
+            row_step: Point3::new(0.1, 0.45, 0.2),
+            col_step: Point3::new(0.7, 0.05, 0.15),
+            dim: (31, 37),
+        };
+
+        assert_eq!(
+            SimdBackend.resample_plane(&data.view(), &grid, &interpolation),
+            ScalarBackend.resample_plane(&data.view(), &grid, &interpolation)
+        );
+    }
+
+    #[test]
+    fn test_trilinear_volume_matches_scalar() {
+        let data = test_data();
+        let interpolation = Interpolation::Trilinear(Processor::SIMD);
+        let step = Point3::new(0.5, 0.75, 0.4);
+
+        assert_eq!(
+            SimdBackend.resample_volume(&data.view(), (21, 17, 41), step, &interpolation),
+            ScalarBackend.resample_volume(&data.view(), (21, 17, 41), step, &interpolation)
+        );
+    }
+
+    #[test]
+    fn test_to_u8_matches_scalar() {
+        let mut data = test_data();
+        data[[1, 2, 3]] = f32::NAN;
+        data[[1, 2, 4]] = 1e9;
+
+        for axis in 0..3 {
+            let slice = data.index_axis(Axis(axis), 1);
+            assert_eq!(SimdBackend.to_u8(&slice), ScalarBackend.to_u8(&slice));
+        }
+    }
+}
@@ -0,0 +1,381 @@
+use crate::enums::Orientation;
+use crate::geometry::Point3;
+use crate::histogram::Moments;
+use crate::volume::Volume;
+
+use ndarray::s;
+use rayon::iter::IntoParallelIterator;
+use rayon::iter::ParallelIterator;
+use rayon::slice::ParallelSliceMut;
+
+/// A region of interest on a slice
+///
+/// Coordinates are (x = column, y = row) of the slice returned by
+/// [`Volume::get_slice_from_axis`]. Points on stretched images can be
+/// converted through [`PixelMapping`](crate::mapping::PixelMapping). A pixel
+/// belongs to the region if its centre does.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Roi {
+    Rectangle {
+        min: (f32, f32),
+        max: (f32, f32),
+    },
+    Ellipse {
+        center: (f32, f32),
+        radii: (f32, f32),
+    },
+    Polygon(Vec<(f32, f32)>),
+}
+
+/// A region of a volume
+#[derive(Clone, Copy)]
+pub enum Region<'a> {
+    /// Sphere around a centre in voxel coordinates with a radius in mm
+    Sphere { center: Point3, radius: f32 },
+    /// Box between two corners in voxel coordinates, both inclusive
+    Box { min: Point3, max: Point3 },
+    /// All non-zero voxels of a mask volume of the same dimensions
+    Mask(&'a Volume),
+}
+
+/// Intensity statistics of the voxels in a region, NaN voxels are ignored
+#[derive(Clone, Debug, PartialEq)]
+pub struct RoiStatistics {
+    pub count: usize,
+    /// Volume covered by the voxels in mL
+    pub volume_ml: f32,
+    pub mean: f32,
+    /// Population standard deviation
+    pub std: f32,
+    pub min: f32,
+    pub max: f32,
+    pub median: f32,
+    sorted: Vec<f32>,
+}
+
+impl RoiStatistics {
+    /// Compute the statistics of a set of values, skipping NaN, `None` if
+    /// no other value remains
+    fn new(mut values: Vec<f32>, voxel_volume_ml: f32) -> Option<Self> {
+        values.retain(|value| !value.is_nan());
+        if values.is_empty() {
+            return None;
+        }
+        values.par_sort_unstable_by(f32::total_cmp);
+
+        let moments = values.iter().copied().fold(Moments::EMPTY, Moments::add);
+        let mut statistics = Self {
+            count: moments.count,
+            volume_ml: moments.count as f32 * voxel_volume_ml,
+            mean: moments.mean as f32,
+            std: moments.std(),
+            min: moments.min,
+            max: moments.max,
+            median: 0.0,
+            sorted: values,
+        };
+        statistics.median = statistics.percentile(50.0);
+        Some(statistics)
+    }
+
+    /// Get a percentile in 0..=100, interpolating linearly between the
+    /// closest ranks
+    pub fn percentile(&self, percentile: f32) -> f32 {
+        let rank = (percentile.clamp(0.0, 100.0) / 100.0) * (self.count - 1) as f32;
+        let lower = rank.floor() as usize;
+        let upper = rank.ceil() as usize;
+        let t = rank - lower as f32;
+        self.sorted[lower] + (self.sorted[upper] - self.sorted[lower]) * t
+    }
+}
+
+impl Roi {
+    /// Get the (min, max) corners of the bounding box
+    fn bounds(&self) -> ((f32, f32), (f32, f32)) {
+        match self {
+            Roi::Rectangle { min, max } => (
+                (min.0.min(max.0), min.1.min(max.1)),
+                (min.0.max(max.0), min.1.max(max.1)),
+            ),
+            Roi::Ellipse { center, radii } => (
+                (center.0 - radii.0.abs(), center.1 - radii.1.abs()),
+                (center.0 + radii.0.abs(), center.1 + radii.1.abs()),
+            ),
+            Roi::Polygon(points) => points.iter().fold(
+                (
+                    (f32::INFINITY, f32::INFINITY),
+                    (f32::NEG_INFINITY, f32::NEG_INFINITY),
+                ),
+                |(min, max), &(x, y)| ((min.0.min(x), min.1.min(y)), (max.0.max(x), max.1.max(y))),
+            ),
+        }
+    }
+
+    /// Whether a point lies within the region
+    pub fn contains(&self, x: f32, y: f32) -> bool {
+        match self {
+            Roi::Rectangle { .. } => {
+                let (min, max) = self.bounds();
+                (min.0..=max.0).contains(&x) && (min.1..=max.1).contains(&y)
+            }
+            Roi::Ellipse { center, radii } => {
+                let dx = (x - center.0) / radii.0;
+                let dy = (y - center.1) / radii.1;
+                dx * dx + dy * dy <= 1.0
+            }
+            Roi::Polygon(points) => {
+                // Even-odd rule
+                let mut inside = false;
+                let mut previous = match points.last() {
+                    Some(&point) => point,
+                    None => return false,
+                };
+                for &point in points {
+                    if (point.1 > y) != (previous.1 > y) {
+                        let crossing = point.0
+                            + (y - point.1) / (previous.1 - point.1) * (previous.0 - point.0);
+                        if x < crossing {
+                            inside = !inside;
+                        }
+                    }
+                    previous = point;
+                }
+                inside
+            }
+        }
+    }
+}
+
+impl Region<'_> {
+    /// Get the (min, max) voxel index range to visit per axis (z, y, x),
+    /// `None` if the region does not overlap the volume
+    fn index_ranges(&self, volume: &Volume) -> Option<[(usize, usize); 3]> {
+        let (depth, height, width) = volume.dim();
+        let (min, max) = match *self {
+            Region::Sphere { center, radius } => {
+                let extent = Point3::new(
+                    radius / volume.spacing.0,
+                    radius / volume.spacing.1,
+                    radius / volume.spacing.2,
+                );
+                (center - extent, center + extent)
+            }
+            Region::Box { min, max } => (
+                Point3::new(min.x.min(max.x), min.y.min(max.y), min.z.min(max.z)),
+                Point3::new(min.x.max(max.x), min.y.max(max.y), min.z.max(max.z)),
+            ),
+            Region::Mask(_) => return Some([(0, depth), (0, height), (0, width)]),
+        };
+        let range = |min: f32, max: f32, len: usize| {
+            let start = min.ceil().max(0.0) as usize;
+            let end = (max.floor() + 1.0).clamp(0.0, len as f32) as usize;
+            (start < end).then_some((start, end))
+        };
+        Some([
+            range(min.z, max.z, depth)?,
+            range(min.y, max.y, height)?,
+            range(min.x, max.x, width)?,
+        ])
+    }
+
+    fn contains(&self, volume: &Volume, (z, y, x): (usize, usize, usize)) -> bool {
+        match *self {
+            Region::Sphere { center, radius } => {
+                let offset =
+                    (Point3::new(x as f32, y as f32, z as f32) - center).scale(volume.spacing);
+                offset.length() <= radius
+            }
+            Region::Box { .. } => true,
+            Region::Mask(mask) => mask.data[[z, y, x]] != 0.0,
+        }
+    }
+}
+
+impl Volume {
+    /// Get the volume of a single voxel in mL
+    pub fn voxel_volume_ml(&self) -> f32 {
+        self.spacing.0 * self.spacing.1 * self.spacing.2 / 1000.0
+    }
+
+    /// Get the statistics of a region of interest on a slice
+    ///
+    /// Returns `None` if the index is out of bounds or the region contains
+    /// no pixel other than NaN.
+    pub fn get_roi_statistics(
+        &self,
+        index: usize,
+        orientation: Orientation,
+        roi: &Roi,
+    ) -> Option<RoiStatistics> {
+        let slice = self.get_slice_from_axis(index, &orientation)?;
+        let (height, width) = slice.dim();
+        let ((min_x, min_y), (max_x, max_y)) = roi.bounds();
+        let rows = min_y.ceil().max(0.0) as usize
+            ..(max_y.floor() + 1.0).clamp(0.0, height as f32) as usize;
+        let cols =
+            min_x.ceil().max(0.0) as usize..(max_x.floor() + 1.0).clamp(0.0, width as f32) as usize;
+
+        let values = rows
+            .flat_map(|row| cols.clone().map(move |col| (row, col)))
+            .filter(|&(row, col)| roi.contains(col as f32, row as f32))
+            .map(|(row, col)| slice[[row, col]])
+            .collect();
+        RoiStatistics::new(values, self.voxel_volume_ml())
+    }
+
+    /// Get the statistics of a 3D region, visiting slices in parallel
+    ///
+    /// Returns `None` if the region contains no voxel other than NaN or a
+    /// mask does not match the dimensions of the volume.
+    pub fn get_region_statistics(&self, region: &Region) -> Option<RoiStatistics> {
+        if let Region::Mask(mask) = region
+            && mask.dim() != self.dim()
+        {
+            return None;
+        }
+        let [(z0, z1), (y0, y1), (x0, x1)] = region.index_ranges(self)?;
+
+        let values = (z0..z1)
+            .into_par_iter()
+            .flat_map_iter(|z| {
+                let slice = self.data.slice(s![z, y0..y1, x0..x1]);
+                slice
+                    .indexed_iter()
+                    .filter(|&((y, x), _)| region.contains(self, (z, y0 + y, x0 + x)))
+                    .map(|(_, &value)| value)
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        RoiStatistics::new(values, self.voxel_volume_ml())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::Array3;
+
+    /// Intensity equals the column index, voxels of 1 × 1 × 2 mm
+    fn test_volume() -> Volume {
+        let data = Array3::from_shape_fn((6, 10, 10), |(_, _, x)| x as f32);
+        Volume::new(data, (1.0, 1.0, 2.0))
+    }
+
+    #[test]
+    fn test_rectangle_statistics() {
+        let volume = test_volume();
+        let roi = Roi::Rectangle {
+            min: (1.0, 2.0),
+            max: (4.0, 3.0),
+        };
+
+        let statistics = volume
+            .get_roi_statistics(0, Orientation::Axial, &roi)
+            .unwrap();
+
+        assert_eq!(statistics.count, 8);
+        assert_eq!((statistics.min, statistics.max), (1.0, 4.0));
+        assert_eq!(statistics.mean, 2.5);
+        assert_eq!(statistics.median, 2.5);
+        assert!((statistics.std - 1.25f32.sqrt()).abs() < 1e-6);
+        assert_eq!(statistics.percentile(100.0), 4.0);
+        assert!((statistics.volume_ml - 0.016).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_ellipse_and_polygon() {
+        let volume = test_volume();
+        let ellipse = Roi::Ellipse {
+            center: (5.0, 3.0),
+            radii: (2.0, 1.0),
+        };
+        let triangle = Roi::Polygon(vec![(0.5, 0.5), (4.5, 0.5), (0.5, 4.5)]);
+
+        let ellipse = volume
+            .get_roi_statistics(3, Orientation::Coronal, &ellipse)
+            .unwrap();
+        let triangle = volume
+            .get_roi_statistics(3, Orientation::Axial, &triangle)
+            .unwrap();
+
+        // Centre row of 5 pixels and a single pixel above and below
+        assert_eq!(ellipse.count, 7);
+        assert_eq!(ellipse.mean, 5.0);
+        // Pixel centres (x, y) in 1..=4 with x + y < 5
+        assert_eq!(triangle.count, 6);
+        assert_eq!(triangle.max, 3.0);
+    }
+
+    #[test]
+    fn test_sphere_respects_spacing() {
+        let volume = test_volume();
+        let sphere = Region::Sphere {
+            center: Point3::new(5.0, 5.0, 2.0),
+            radius: 2.0,
+        };
+
+        let statistics = volume.get_region_statistics(&sphere).unwrap();
+
+        // 13 voxels in the central slice and one above and below
+        assert_eq!(statistics.count, 15);
+        assert_eq!(statistics.mean, 5.0);
+        assert!((statistics.volume_ml - 0.03).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_box_and_mask_regions() {
+        let volume = test_volume();
+        let mut mask = Volume::new(Array3::zeros((6, 10, 10)), (1.0, 1.0, 2.0));
+        mask.data_mut().slice_mut(s![1..3, 0..2, 8..]).fill(1.0);
+
+        let cube = volume
+            .get_region_statistics(&Region::Box {
+                min: Point3::new(2.0, 0.0, 0.0),
+                max: Point3::new(3.0, 1.0, 1.0),
+            })
+            .unwrap();
+        let masked = volume.get_region_statistics(&Region::Mask(&mask)).unwrap();
+
+        assert_eq!((cube.count, cube.mean), (8, 2.5));
+        assert_eq!((masked.count, masked.mean), (8, 8.5));
+        assert!(
+            volume
+                .get_region_statistics(&Region::Mask(&Volume::default()))
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_statistics_ignore_nan() {
+        let mut volume = test_volume();
+        volume.data_mut()[[0, 2, 9]] = f32::NAN;
+        volume.data_mut()[[1, 3, 9]] = f32::NAN;
+        let roi = Roi::Rectangle {
+            min: (8.0, 2.0),
+            max: (9.0, 2.0),
+        };
+        let region = Region::Box {
+            min: Point3::new(8.0, 2.0, 0.0),
+            max: Point3::new(9.0, 3.0, 1.0),
+        };
+
+        let slice = volume
+            .get_roi_statistics(0, Orientation::Axial, &roi)
+            .unwrap();
+        let cube = volume.get_region_statistics(&region).unwrap();
+
+        assert_eq!((slice.count, slice.mean, slice.max), (1, 8.0, 8.0));
+        assert_eq!(slice.std, 0.0);
+        assert_eq!((cube.count, cube.min, cube.max), (6, 8.0, 9.0));
+        assert!((cube.mean - 50.0 / 6.0).abs() < 1e-6);
+        assert_eq!(cube.percentile(100.0), 9.0);
+        assert!((cube.volume_ml - 0.012).abs() < 1e-6);
+
+        volume.data_mut()[[0, 2, 8]] = f32::NAN;
+        assert!(
+            volume
+                .get_roi_statistics(0, Orientation::Axial, &roi)
+                .is_none()
+        );
+    }
+}
@@ -1,20 +1,50 @@
+use crate::backend::PlaneGrid;
 use crate::enums::Interpolation;
 use crate::enums::Orientation;
+use crate::enums::Processor;
+use crate::geometry::IDENTITY_DIRECTION;
+use crate::geometry::Point3;
+use crate::histogram::VolumeStatistics;
 use crate::interpolator::Interpolator;
 
 use image::ImageBuffer;
 use image::Luma;
+use ndarray::Array2;
 use ndarray::Array3;
 use ndarray::ArrayView2;
+use ndarray::ArrayView3;
+use ndarray::Axis;
+use ndarray::CowArray;
+use ndarray::Ix2;
 use ndarray::s;
-use rayon::iter::IntoParallelIterator;
-use rayon::iter::ParallelIterator;
+use std::sync::OnceLock;
 
-#[derive(Default)]
 pub struct Volume {
     pub data: Array3<f32>,
     pub spacing: (f32, f32, f32),
     pub interpolated_dim: (u32, u32, u32),
+    /// Patient position (mm) of the center of voxel (0, 0, 0)
+    pub origin: Point3,
+    /// Patient space unit vectors of the voxel x, y and z axes
+    pub direction: [Point3; 3],
+    /// Cached result of [`Volume::statistics`]
+    pub(crate) statistics: OnceLock<Option<VolumeStatistics>>,
+    /// Cached result of [`Volume::bspline_coefficients`]
+    pub(crate) bspline: OnceLock<Array3<f32>>,
+}
+
+impl Default for Volume {
+    fn default() -> Self {
+        Self {
+            data: Array3::default((0, 0, 0)),
+            spacing: (0.0, 0.0, 0.0),
+            interpolated_dim: (0, 0, 0),
+            origin: Point3::default(),
+            direction: IDENTITY_DIRECTION,
+            statistics: OnceLock::new(),
+            bspline: OnceLock::new(),
+        }
+    }
 }
 
 impl Volume {
@@ -24,9 +54,46 @@ impl Volume {
             data,
             spacing,
             interpolated_dim: Interpolator::get_isotropic_dimensions(spacing, original_dim),
+            origin: Point3::default(),
+            direction: IDENTITY_DIRECTION,
+            statistics: OnceLock::new(),
+            bspline: OnceLock::new(),
         }
     }
 
+    /// Set the patient space position and axis directions of the volume
+    pub fn with_geometry(mut self, origin: Point3, direction: [Point3; 3]) -> Self {
+        self.origin = origin;
+        self.direction = direction;
+        self
+    }
+
+    /// Convert continuous voxel coordinates (x = column, y = row, z = slice)
+    /// to patient coordinates in mm
+    pub fn voxel_to_patient(&self, voxel: Point3) -> Point3 {
+        let mm = voxel.scale(self.spacing);
+        self.origin + self.direction[0] * mm.x + self.direction[1] * mm.y + self.direction[2] * mm.z
+    }
+
+    /// Convert patient coordinates in mm to continuous voxel coordinates
+    ///
+    /// The axis directions are assumed to be orthonormal.
+    pub fn patient_to_voxel(&self, patient: Point3) -> Point3 {
+        let offset = patient - self.origin;
+        Point3::new(
+            offset.dot(&self.direction[0]) / self.spacing.0,
+            offset.dot(&self.direction[1]) / self.spacing.1,
+            offset.dot(&self.direction[2]) / self.spacing.2,
+        )
+    }
+
+    /// Get the patient coordinates of the centre of the volume
+    pub fn center(&self) -> Point3 {
+        let (depth, height, width) = self.dim();
+        let last = |len: usize| len.saturating_sub(1) as f32;
+        self.voxel_to_patient(Point3::new(last(width), last(height), last(depth)) * 0.5)
+    }
+
     /// Get the dimensions of the volume (depth, height, width)
     pub fn dim(&self) -> (usize, usize, usize) {
         self.data.dim()
@@ -38,12 +105,40 @@ impl Volume {
     }
 
     /// Get a mutable reference to the underlying data
+    ///
+    /// Clears cached results computed from the data.
     pub fn data_mut(&mut self) -> &mut Array3<f32> {
+        self.clear_cache();
         &mut self.data
     }
 
+    /// Clear cached results computed from the data, required after
+    /// modifying the `data` field directly
+    pub fn clear_cache(&mut self) {
+        self.statistics = OnceLock::new();
+        self.bspline = OnceLock::new();
+    }
+
+    /// Get the cubic B-spline coefficients of the data, computed on first use
+    pub fn bspline_coefficients(&self) -> &Array3<f32> {
+        self.bspline.get_or_init(|| {
+            let mut coefficients = self.data.clone();
+            Interpolator::bspline_prefilter(&mut coefficients);
+            coefficients
+        })
+    }
+
+    /// Get the data to sample with `interpolation` from, i.e. the B-spline
+    /// coefficients for `BSpline` and the data otherwise
+    pub(crate) fn samples(&self, interpolation: &Interpolation) -> ArrayView3<'_, f32> {
+        match interpolation {
+            Interpolation::BSpline(_) => self.bspline_coefficients().view(),
+            _ => self.data.view(),
+        }
+    }
+
     #[inline]
-    fn normalize_to_u8(value: f32) -> u8 {
+    pub(crate) fn normalize_to_u8(value: f32) -> u8 {
         ((value / 65535.0) * 255.0).clamp(0.0, 255.0) as u8
     }
 
@@ -71,20 +166,39 @@ impl Volume {
         Some(slice_result)
     }
 
-    fn get_plane_spacing(&self, orientation: &Orientation) -> (u32, u32) {
+    /// Get the distance in mm between two adjacent slices of the given orientation
+    pub(crate) fn get_slice_spacing(&self, orientation: &Orientation) -> f32 {
+        match orientation {
+            Orientation::Axial => self.spacing.2,
+            Orientation::Coronal => self.spacing.1,
+            Orientation::Sagittal => self.spacing.0,
+        }
+    }
+
+    /// Get the spacing (row, column) in mm of the slices of an orientation
+    pub(crate) fn get_pixel_spacing(&self, orientation: &Orientation) -> (f32, f32) {
         match orientation {
-            Orientation::Axial => (self.interpolated_dim.1, self.interpolated_dim.2), // (height, width)
-            Orientation::Coronal => (self.interpolated_dim.1, self.interpolated_dim.0), // (height, depth)
-            Orientation::Sagittal => (self.interpolated_dim.2, self.interpolated_dim.0), // (width, depth)
+            Orientation::Axial => (self.spacing.1, self.spacing.0),
+            Orientation::Coronal => (self.spacing.2, self.spacing.0),
+            Orientation::Sagittal => (self.spacing.2, self.spacing.1),
+        }
+    }
+
+    /// Get the (width, height) of an isotropic image of the given orientation
+    pub(crate) fn get_plane_spacing(&self, orientation: &Orientation) -> (u32, u32) {
+        match orientation {
+            Orientation::Axial => (self.interpolated_dim.2, self.interpolated_dim.1), // (width, height)
+            Orientation::Coronal => (self.interpolated_dim.2, self.interpolated_dim.0), // (width, depth)
+            Orientation::Sagittal => (self.interpolated_dim.1, self.interpolated_dim.0), // (height, depth)
         }
     }
     // Extract slice to image conversion
-    fn slice_to_image(slice: &ArrayView2<'_, f32>) -> Option<ImageBuffer<Luma<u8>, Vec<u8>>> {
+    pub(crate) fn slice_to_image(
+        slice: &ArrayView2<'_, f32>,
+        processor: &Processor,
+    ) -> Option<ImageBuffer<Luma<u8>, Vec<u8>>> {
         let (height, width) = slice.dim();
-        let pixel_data: Vec<u8> = slice
-            .into_par_iter()
-            .map(|&v| Self::normalize_to_u8(v))
-            .collect();
+        let pixel_data = processor.backend().to_u8(slice);
         ImageBuffer::from_raw(width as u32, height as u32, pixel_data)
     }
 
@@ -96,45 +210,272 @@ impl Volume {
         interpolation: Interpolation,
     ) -> Option<ImageBuffer<Luma<u8>, Vec<u8>>> {
         let slice = self.get_slice_from_axis(index, &orientation)?;
+        self.plane_to_image(&slice, orientation, &interpolation)
+    }
 
-        match interpolation {
-            Interpolation::None => Self::slice_to_image(&slice),
-            Interpolation::Bilinear(_) => {
-                // Axial doesn't need interpolation (already isotropic in-plane)
-                if matches!(orientation, Orientation::Axial) {
-                    return Self::slice_to_image(&slice);
-                }
+    /// Convert a plane of the given orientation to an image, stretching it
+    /// to the isotropic aspect ratio if requested
+    pub(crate) fn plane_to_image(
+        &self,
+        plane: &ArrayView2<'_, f32>,
+        orientation: Orientation,
+        interpolation: &Interpolation,
+    ) -> Option<ImageBuffer<Luma<u8>, Vec<u8>>> {
+        let plane = self.stretch_plane(plane.view(), orientation, interpolation);
+        Self::slice_to_image(&plane.view(), interpolation.processor())
+    }
+
+    /// Stretch a plane of the given orientation to the isotropic aspect
+    /// ratio unless `interpolation` is `None`
+    pub(crate) fn stretch_plane<'a>(
+        &self,
+        plane: ArrayView2<'a, f32>,
+        orientation: Orientation,
+        interpolation: &Interpolation,
+    ) -> CowArray<'a, f32, Ix2> {
+        // Axial doesn't need interpolation (already isotropic in-plane)
+        if matches!(interpolation, Interpolation::None) || matches!(orientation, Orientation::Axial)
+        {
+            return plane.into();
+        }
+
+        let (target_width, target_height) = self.get_plane_spacing(&orientation);
+        let backend = interpolation.processor().backend();
+        backend
+            .resample_slice(
+                &plane,
+                (target_height as usize, target_width as usize),
+                interpolation,
+            )
+            .into()
+    }
+
+    /// Get a slice at a fractional index, e.g. to place a coronal slice at
+    /// an exact mm position obtained through [`Volume::patient_to_voxel`]
+    ///
+    /// Unless `interpolation` is `None`, Coronal and Sagittal slices are
+    /// resampled to the isotropic aspect ratio like in [`Volume::get_image_from_axis`].
+    pub fn get_slice_at_position(
+        &self,
+        position: f32,
+        orientation: Orientation,
+        interpolation: Interpolation,
+    ) -> Option<Array2<f32>> {
+        let grid = self.get_plane_grid(position, orientation, &interpolation)?;
+        let backend = interpolation.processor().backend();
+        Some(backend.resample_plane(&self.samples(&interpolation), &grid, &interpolation))
+    }
+
+    /// Get the voxel positions sampled by [`Volume::get_slice_at_position`]
+    pub(crate) fn get_plane_grid(
+        &self,
+        position: f32,
+        orientation: Orientation,
+        interpolation: &Interpolation,
+    ) -> Option<PlaneGrid> {
+        let len = self.data.len_of(Axis(orientation.axis()));
+        if self.data.is_empty() || !(0.0..=(len - 1) as f32).contains(&position) {
+            return None;
+        }
 
+        let dim = self.data.dim();
+        let (height, width) = match orientation {
+            Orientation::Axial => (dim.1, dim.2),
+            Orientation::Coronal => (dim.0, dim.2),
+            Orientation::Sagittal => (dim.0, dim.1),
+        };
+        let (target_height, target_width) = match (interpolation, orientation) {
+            (Interpolation::None, _) | (_, Orientation::Axial) => (height, width),
+            _ => {
                 let (target_width, target_height) = self.get_plane_spacing(&orientation);
-                self.interpolate_slice(&slice, target_width, target_height)
+                (target_height as usize, target_width as usize)
             }
-        }
+        };
+        let scale_y = (height - 1) as f32 / (target_height - 1).max(1) as f32;
+        let scale_x = (width - 1) as f32 / (target_width - 1).max(1) as f32;
+        let (origin, row_step, col_step) = match orientation {
+            Orientation::Axial => (
+                Point3::new(0.0, 0.0, position),
+                Point3::new(0.0, scale_y, 0.0),
+                Point3::new(scale_x, 0.0, 0.0),
+            ),
+            Orientation::Coronal => (
+                Point3::new(0.0, position, 0.0),
+                Point3::new(0.0, 0.0, scale_y),
+                Point3::new(scale_x, 0.0, 0.0),
+            ),
+            Orientation::Sagittal => (
+                Point3::new(position, 0.0, 0.0),
+                Point3::new(0.0, 0.0, scale_y),
+                Point3::new(0.0, scale_x, 0.0),
+            ),
+        };
+        Some(PlaneGrid {
+            origin,
+            row_step,
+            col_step,
+            dim: (target_height, target_width),
+        })
     }
 
-    fn interpolate_slice(
+    /// Get a slice at a fractional index as image
+    pub fn get_image_at_position(
         &self,
-        slice: &ArrayView2<'_, f32>,
-        target_width: u32,
-        target_height: u32,
+        position: f32,
+        orientation: Orientation,
+        interpolation: Interpolation,
     ) -> Option<ImageBuffer<Luma<u8>, Vec<u8>>> {
-        let (height, width) = slice.dim();
-        let scale_x = (width - 1) as f32 / (target_width - 1).max(1) as f32;
-        let scale_y = (height - 1) as f32 / (target_height - 1).max(1) as f32;
+        let processor = interpolation.processor().clone();
+        let slice = self.get_slice_at_position(position, orientation, interpolation)?;
+        Self::slice_to_image(&slice.view(), &processor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_volume() -> Volume {
+        // Intensity equals the row index
+        let data = Array3::from_shape_fn((3, 4, 5), |(_, y, _)| y as f32);
+        Volume::new(data, (1.0, 1.0, 2.0))
+    }
+
+    #[test]
+    fn test_coronal_slice_at_fractional_position() {
+        let volume = test_volume();
+
+        let slice = volume
+            .get_slice_at_position(
+                1.5,
+                Orientation::Coronal,
+                Interpolation::Trilinear(Processor::CPU),
+            )
+            .unwrap();
+
+        // Depth is stretched from 3 slices at 2 mm to 6 isotropic rows
+        assert_eq!(slice.dim(), (6, 5));
+        assert!(slice.iter().all(|&v| (v - 1.5).abs() < 1e-6));
+    }
+
+    #[test]
+    fn test_bilinear_coronal_slice_interpolates_depth() {
+        // Intensity ramps along the depth of thick slices
+        let data = Array3::from_shape_fn((4, 3, 5), |(z, _, _)| 10.0 * z as f32);
+        let volume = Volume::new(data, (1.0, 1.0, 3.0));
+
+        let bilinear = volume
+            .get_slice_at_position(
+                1.0,
+                Orientation::Coronal,
+                Interpolation::Bilinear(Processor::CPU),
+            )
+            .unwrap();
+        let trilinear = volume
+            .get_slice_at_position(
+                1.0,
+                Orientation::Coronal,
+                Interpolation::Trilinear(Processor::CPU),
+            )
+            .unwrap();
+
+        // 4 slices at 3 mm stretched to 12 rows, a ramp instead of a staircase
+        assert_eq!(bilinear.dim(), (12, 5));
+        assert_eq!(bilinear, trilinear);
+        for (row, values) in bilinear.outer_iter().enumerate() {
+            let expected = 30.0 * row as f32 / 11.0;
+            assert!(values.iter().all(|&v| (v - expected).abs() < 1e-4));
+        }
+    }
+
+    #[test]
+    fn test_nearest_neighbor_stretches_label_slice() {
+        let data = Array3::from_shape_fn((3, 4, 4), |(z, _, x)| ((z + x) % 2) as f32);
+        let volume = Volume::new(data, (1.0, 1.0, 2.5));
+
+        let slice = volume
+            .get_slice_at_position(
+                1.0,
+                Orientation::Coronal,
+                Interpolation::NearestNeighbor(Processor::CPU),
+            )
+            .unwrap();
+
+        assert_eq!(slice.dim(), (7, 4));
+        assert!(slice.iter().all(|&v| v == 0.0 || v == 1.0));
+    }
+
+    #[test]
+    fn test_slice_at_integer_position_matches_axis_slice() {
+        let volume = test_volume();
+
+        let slice = volume
+            .get_slice_at_position(2.0, Orientation::Axial, Interpolation::None)
+            .unwrap();
+
+        assert_eq!(
+            slice.view(),
+            volume.get_slice_from_axis(2, &Orientation::Axial).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_stretched_images_keep_in_plane_width() {
+        let volume = Volume::new(Array3::zeros((3, 4, 5)), (1.0, 1.0, 2.0));
+
+        let coronal = volume
+            .get_image_from_axis(
+                0,
+                Orientation::Coronal,
+                Interpolation::Bilinear(Processor::CPU),
+            )
+            .unwrap();
+        let sagittal = volume
+            .get_image_from_axis(
+                0,
+                Orientation::Sagittal,
+                Interpolation::Bilinear(Processor::CPU),
+            )
+            .unwrap();
+
+        assert_eq!(coronal.dimensions(), (5, 6));
+        assert_eq!(sagittal.dimensions(), (4, 6));
+    }
+
+    #[test]
+    fn test_bspline_coefficients_are_cached_until_data_changes() {
+        let data = Array3::from_shape_fn((3, 4, 5), |(z, y, x)| ((z * 7 + y * 3 + x) % 4) as f32);
+        let mut volume = Volume::new(data, (1.0, 1.0, 1.0));
+        let interpolation = || Interpolation::BSpline(Processor::CPU);
+
+        let first = volume.bspline_coefficients() as *const Array3<f32>;
+        let slice = volume
+            .get_slice_at_position(1.0, Orientation::Axial, interpolation())
+            .unwrap();
+
+        // B-splines interpolate, so the samples are reproduced on the grid
+        assert_eq!(volume.bspline_coefficients() as *const Array3<f32>, first);
+        assert!(
+            slice
+                .iter()
+                .zip(volume.data.index_axis(Axis(0), 1))
+                .all(|(a, b)| (a - b).abs() < 1e-4)
+        );
+
+        volume.data_mut().fill(2.0);
+        let slice = volume
+            .get_slice_at_position(1.0, Orientation::Axial, interpolation())
+            .unwrap();
+
+        assert!(slice.iter().all(|&v| (v - 2.0).abs() < 1e-4));
+    }
+
+    #[test]
+    fn test_slice_at_position_out_of_bounds() {
+        let volume = test_volume();
+
+        let result = volume.get_slice_at_position(3.5, Orientation::Coronal, Interpolation::None);
 
-        let pixel_data: Vec<u8> = (0..target_height)
-            .into_par_iter()
-            .flat_map(|row| {
-                (0..target_width)
-                    .map(|col| {
-                        let src_y = row as f32 * scale_y;
-                        let src_x = col as f32 * scale_x;
-                        let value = Interpolator::bilinear_interpolate(slice, src_y, src_x);
-                        Self::normalize_to_u8(value)
-                    })
-                    .collect::<Vec<u8>>()
-            })
-            .collect();
-
-        ImageBuffer::from_raw(target_width, target_height, pixel_data)
+        assert!(result.is_none());
     }
 }
@@ -1,4 +1,8 @@
-use crate::{enums::SortBy, volume::Volume};
+use crate::{
+    enums::SortBy,
+    geometry::{IDENTITY_DIRECTION, Point3},
+    volume::Volume,
+};
 
 use dicom::{
     object::{FileDicomObject, InMemDicomObject, open_file},
@@ -55,17 +59,26 @@ impl VolumeLoader {
 
         Self::sort_images(&mut images_with_order, sort_by);
 
+        let first_position = images_with_order
+            .first()
+            .and_then(|(_, position, _)| *position);
+        let last_position = images_with_order
+            .last()
+            .and_then(|(_, position, _)| *position);
+
         let images: Vec<_> = images_with_order
             .into_iter()
-            .map(|(_, image)| image)
+            .map(|(_, _, image)| image)
             .collect();
 
         Self::validate_dimensions(&images)?;
 
         let volume_array = Self::build_volume_array(&images);
         let spacing = Self::get_spacing(dicom_objects).ok_or(VolumeLoaderError::MissingSpacing)?;
+        let direction = Self::get_direction(dicom_objects, first_position, last_position);
 
-        Ok(Volume::new(volume_array, spacing))
+        Ok(Volume::new(volume_array, spacing)
+            .with_geometry(first_position.unwrap_or_default(), direction))
     }
 
     /// Load a volume from file paths
@@ -104,10 +117,19 @@ impl VolumeLoader {
     fn extract_image_with_order(
         dicom_object: &FileDicomObject<InMemDicomObject>,
         sort_by: &SortBy,
-    ) -> Option<(Option<f32>, Array2<f32>)> {
+    ) -> Option<(Option<f32>, Option<Point3>, Array2<f32>)> {
         let order = Self::get_sort_order(dicom_object, sort_by)?;
         let image_2d = Self::decode_image(dicom_object)?;
-        Some((order, image_2d))
+        Some((order, Self::get_position(dicom_object), image_2d))
+    }
+
+    fn get_position(dicom_object: &FileDicomObject<InMemDicomObject>) -> Option<Point3> {
+        let pos = dicom_object
+            .element(tags::IMAGE_POSITION_PATIENT)
+            .ok()?
+            .to_multi_float32()
+            .ok()?;
+        (pos.len() == 3).then(|| Point3::new(pos[0], pos[1], pos[2]))
     }
 
     fn get_sort_order(
@@ -155,7 +177,10 @@ impl VolumeLoader {
             .map(|arr| arr.slice_move(s![0, .., .., 0]))
     }
 
-    fn sort_images(images_with_order: &mut [(Option<f32>, Array2<f32>)], sort_by: SortBy) {
+    fn sort_images(
+        images_with_order: &mut [(Option<f32>, Option<Point3>, Array2<f32>)],
+        sort_by: SortBy,
+    ) {
         if !matches!(sort_by, SortBy::None) {
             images_with_order
                 .sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
@@ -207,4 +232,45 @@ impl VolumeLoader {
             Some((pixel_spacing[0], pixel_spacing[1], slice_thickness))
         })
     }
+
+    /// Get the patient space directions of the voxel axes. The slice axis
+    /// follows the order of the sorted images if their positions are known.
+    fn get_direction(
+        dicom_objects: &[FileDicomObject<InMemDicomObject>],
+        first_position: Option<Point3>,
+        last_position: Option<Point3>,
+    ) -> [Point3; 3] {
+        let orientation = dicom_objects.iter().find_map(|dicom_object| {
+            let cosines = dicom_object
+                .element(tags::IMAGE_ORIENTATION_PATIENT)
+                .ok()?
+                .to_multi_float32()
+                .ok()?;
+            (cosines.len() == 6).then(|| {
+                (
+                    Point3::new(cosines[0], cosines[1], cosines[2]),
+                    Point3::new(cosines[3], cosines[4], cosines[5]),
+                )
+            })
+        });
+
+        let Some((row, column)) = orientation else {
+            return IDENTITY_DIRECTION;
+        };
+
+        let normal = row.cross(&column);
+        let slice = match (first_position, last_position) {
+            (Some(first), Some(last)) => (last - first).normalize().map(|step| {
+                // Keep the slice axis perpendicular to the image plane
+                if step.dot(&normal) < 0.0 {
+                    -normal
+                } else {
+                    normal
+                }
+            }),
+            _ => None,
+        };
+
+        [row, column, slice.unwrap_or(normal)]
+    }
 }